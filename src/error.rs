@@ -0,0 +1,160 @@
+// -*- coding: utf-8 -*-
+//
+// Iterator Peekable with multi-forward-peek and multi-backward-peek
+//
+// Copyright 2024 Michael Büsch <m@bues.ch>
+//
+// Licensed under the Apache License version 2.0
+// or the MIT license, at your option.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+//
+
+//! Error type of the fallible [crate::Peekable] operations.
+
+use core::fmt;
+
+/// Error of the fallible [crate::Peekable] operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PeekError {
+    /// The requested index does not fit into the peek buffer.
+    OverCapacity {
+        /// The requested index.
+        requested: usize,
+        /// The capacity of the peek buffer.
+        capacity: usize,
+    },
+    /// The inner iterator is exhausted.
+    Exhausted,
+    /// The element does not match the expected value.
+    Mismatch {
+        /// The absolute stream position of the mismatching element.
+        position: usize,
+    },
+    /// The consume limit has been reached before the search finished.
+    ///
+    /// See [crate::Peekable::set_consume_limit].
+    LimitReached {
+        /// The consume limit.
+        limit: usize,
+    },
+}
+
+impl fmt::Display for PeekError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OverCapacity {
+                requested,
+                capacity,
+            } => write!(
+                f,
+                "Requested index {requested} exceeds the peek buffer capacity {capacity}"
+            ),
+            Self::Exhausted => write!(f, "The iterator is exhausted"),
+            Self::Mismatch { position } => {
+                write!(f, "Unexpected element at position {position}")
+            }
+            Self::LimitReached { limit } => {
+                write!(f, "The consume limit {limit} has been reached")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    /// Fixed size [fmt::Write] buffer for testing.
+    struct Buf {
+        buf: [u8; 128],
+        len: usize,
+    }
+
+    impl Buf {
+        fn new() -> Self {
+            Self {
+                buf: [0; 128],
+                len: 0,
+            }
+        }
+
+        fn as_str(&self) -> &str {
+            core::str::from_utf8(&self.buf[..self.len]).unwrap()
+        }
+    }
+
+    impl Write for Buf {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.buf[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let mut b = Buf::new();
+        write!(
+            b,
+            "{}",
+            PeekError::OverCapacity {
+                requested: 5,
+                capacity: 4
+            }
+        )
+        .unwrap();
+        assert_eq!(
+            b.as_str(),
+            "Requested index 5 exceeds the peek buffer capacity 4"
+        );
+
+        let mut b = Buf::new();
+        write!(b, "{}", PeekError::Exhausted).unwrap();
+        assert_eq!(b.as_str(), "The iterator is exhausted");
+
+        let mut b = Buf::new();
+        write!(b, "{}", PeekError::Mismatch { position: 3 }).unwrap();
+        assert_eq!(b.as_str(), "Unexpected element at position 3");
+
+        let mut b = Buf::new();
+        write!(b, "{}", PeekError::LimitReached { limit: 7 }).unwrap();
+        assert_eq!(b.as_str(), "The consume limit 7 has been reached");
+    }
+
+    #[test]
+    fn test_eq() {
+        assert_eq!(PeekError::Exhausted, PeekError::Exhausted.clone());
+        assert_eq!(
+            PeekError::OverCapacity {
+                requested: 1,
+                capacity: 2
+            },
+            PeekError::OverCapacity {
+                requested: 1,
+                capacity: 2
+            }
+        );
+        assert_ne!(
+            PeekError::OverCapacity {
+                requested: 1,
+                capacity: 2
+            },
+            PeekError::OverCapacity {
+                requested: 2,
+                capacity: 2
+            }
+        );
+        assert_ne!(
+            PeekError::OverCapacity {
+                requested: 1,
+                capacity: 2
+            },
+            PeekError::Exhausted
+        );
+    }
+}
+
+// vim: ts=4 sw=4 expandtab
@@ -12,6 +12,8 @@
 //! Iterator Peekable with multi-forward-peek and multi-backward-peek
 //!
 //! This crate is `#![no_std]`, does not heap-allocate and does not contain `unsafe` code.
+//! Some additional helpers that return heap-allocated collections
+//! are available with the optional `alloc` feature.
 //!
 //! The [Iterator::Item] must implement [Clone].
 //!
@@ -47,8 +49,111 @@
 #![no_std]
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod error;
+
+pub use error::PeekError;
+
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
 use arraydeque::{ArrayDeque, Wrapping};
-use core::iter::Fuse;
+use core::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    iter::{Chain, FlatMap, Flatten, Fuse, Repeat, Successors, Take},
+    ops::Range,
+    str::Chars,
+};
+
+/// Position of an element within the stream.
+///
+/// See [Peekable::with_position].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// The first element of a stream with more than one element.
+    First,
+    /// An element that is neither the first nor the last one.
+    Middle,
+    /// The last element of a stream with more than one element.
+    Last,
+    /// The only element of a stream with exactly one element.
+    Only,
+}
+
+/// Result of a forward peek that models the end of the stream explicitly.
+///
+/// See [Peekable::peek_fwd_eof] and [Peekable::peek_fwd_nth_eof].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeekResult<T> {
+    /// The peeked element.
+    Item(T),
+    /// The inner iterator is exhausted before the peeked position.
+    Eof,
+    /// The peeked position does not fit into the forward peek buffer.
+    OverCapacity,
+}
+
+/// One step of merging two streams.
+///
+/// See [Peekable::merge_step].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeItem<L, R> {
+    /// The element from the left stream was smaller.
+    Left(L),
+    /// The element from the right stream was smaller.
+    Right(R),
+    /// Both elements were equal. Both have been consumed.
+    Both(L, R),
+}
+
+/// Record sentinel and its equality function.
+///
+/// See [Peekable::set_record_sentinel].
+type Sentinel<T> = (T, fn(&T, &T) -> bool);
+
+/// Generate the peek methods of a wrapper type by forwarding them
+/// to its inner [Peekable] in the field `$field`.
+macro_rules! delegate_peek {
+    ($field:ident) => {
+        /// See [Peekable::peek_bwd].
+        #[inline]
+        pub fn peek_bwd(&mut self) -> Option<&I::Item> {
+            self.$field.peek_bwd()
+        }
+
+        /// See [Peekable::peek_fwd].
+        #[inline]
+        pub fn peek_fwd(&mut self) -> Option<&I::Item> {
+            self.$field.peek_fwd()
+        }
+
+        /// See [Peekable::peek].
+        #[inline]
+        pub fn peek(&mut self) -> Option<&I::Item> {
+            self.$field.peek()
+        }
+
+        /// See [Peekable::peek_bwd_nth].
+        #[inline]
+        pub fn peek_bwd_nth(&mut self, i: usize) -> Option<&I::Item> {
+            self.$field.peek_bwd_nth(i)
+        }
+
+        /// See [Peekable::peek_fwd_nth].
+        #[inline]
+        pub fn peek_fwd_nth(&mut self, i: usize) -> Option<&I::Item> {
+            self.$field.peek_fwd_nth(i)
+        }
+
+        /// See [Peekable::peek_nth].
+        #[inline]
+        pub fn peek_nth(&mut self, i: usize) -> Option<&I::Item> {
+            self.$field.peek_nth(i)
+        }
+    };
+}
 
 /// Iterator Peekable with multi-forward-peek and multi-backward-peek
 ///
@@ -64,6 +169,12 @@ where
     iter: Fuse<I>,
     bwd_buf: ArrayDeque<I::Item, BWD_SIZE, Wrapping>,
     fwd_buf: ArrayDeque<I::Item, FWD_SIZE, Wrapping>,
+    pos: usize,
+    track_bwd: bool,
+    deep: usize,
+    consume_limit: Option<usize>,
+    sentinel: Option<Sentinel<I::Item>>,
+    rewindable: usize,
 }
 
 impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Peekable<I, BWD_SIZE, FWD_SIZE>
@@ -84,7 +195,126 @@ where
             iter: iter.into_iter().fuse(),
             bwd_buf: ArrayDeque::new(),
             fwd_buf: ArrayDeque::new(),
+            pos: 0,
+            track_bwd: true,
+            deep: 0,
+            consume_limit: None,
+            sentinel: None,
+            rewindable: 0,
+        }
+    }
+
+    /// Create a new [Peekable] that endlessly repeats `value`.
+    ///
+    /// This wraps [core::iter::repeat].
+    /// All forward peeks within `FWD_SIZE` always return `Some`.
+    ///
+    /// The stream is infinite.
+    /// Therefore, there is no meaningful [Iterator::size_hint] upper bound
+    /// and [ExactSizeIterator] does not apply.
+    #[inline]
+    pub fn repeat<T>(value: T) -> Peekable<Repeat<T>, BWD_SIZE, FWD_SIZE>
+    where
+        T: Clone,
+    {
+        Peekable::<Repeat<T>, BWD_SIZE, FWD_SIZE>::new(core::iter::repeat(value))
+    }
+
+    /// Create a new [Peekable] over a generated sequence.
+    ///
+    /// This wraps [core::iter::successors].
+    /// The sequence starts with `first` and each following element
+    /// is computed from its predecessor by `succ`.
+    ///
+    /// ```
+    /// use peekable_fwd_bwd::Peekable;
+    /// use core::iter::Successors;
+    ///
+    /// let mut iter = Peekable::<Successors<u32, fn(&u32) -> Option<u32>>, 2, 4>::successors(
+    ///     Some(1),
+    ///     |x: &u32| x.checked_mul(2),
+    /// );
+    ///
+    /// assert_eq!(iter.peek_nth(3), Some(&8));
+    /// assert_eq!(iter.next(), Some(1));
+    /// assert_eq!(iter.next(), Some(2));
+    /// assert_eq!(iter.peek(), Some(&4));
+    /// assert_eq!(iter.peek_bwd_nth(0), Some(&2));
+    /// assert_eq!(iter.peek_bwd_nth(1), Some(&1));
+    /// ```
+    #[inline]
+    pub fn successors<T, F>(
+        first: Option<T>,
+        succ: F,
+    ) -> Peekable<Successors<T, F>, BWD_SIZE, FWD_SIZE>
+    where
+        T: Clone,
+        F: FnMut(&T) -> Option<T>,
+    {
+        Peekable::<Successors<T, F>, BWD_SIZE, FWD_SIZE>::new(core::iter::successors(first, succ))
+    }
+
+    /// Create a new [Peekable] over the [char]s of a string.
+    #[inline]
+    pub fn from_chars(s: &str) -> Peekable<Chars<'_>, BWD_SIZE, FWD_SIZE> {
+        Peekable::<Chars<'_>, BWD_SIZE, FWD_SIZE>::new(s.chars())
+    }
+
+    /// Create a new [Peekable] over a [Range].
+    ///
+    /// This is the same as [Self::new] with the range as iterator.
+    #[inline]
+    pub fn from_range<T>(r: Range<T>) -> Peekable<Range<T>, BWD_SIZE, FWD_SIZE>
+    where
+        Range<T>: Iterator<Item = T>,
+        T: Clone,
+    {
+        Peekable::<Range<T>, BWD_SIZE, FWD_SIZE>::new(r)
+    }
+
+    /// Create a new [Peekable] with a pre-seeded backward peek buffer.
+    ///
+    /// `history` holds synthetic past elements, ordered from oldest to newest.
+    /// Only the newest `BWD_SIZE` elements are kept.
+    /// The newest element is returned by [Self::peek_bwd] right after construction.
+    /// The seeded elements are not counted by [Self::stream_pos]
+    /// and they can't be un-consumed with [Self::step_back].
+    pub fn with_bwd_history<II, T>(
+        iter: II,
+        history: T,
+    ) -> Peekable<II::IntoIter, BWD_SIZE, FWD_SIZE>
+    where
+        II: IntoIterator,
+        II::Item: Clone,
+        T: IntoIterator<Item = II::Item>,
+    {
+        let mut peekable = Peekable::<II::IntoIter, BWD_SIZE, FWD_SIZE>::new(iter);
+        for item in history {
+            peekable.bwd_buf.push_front(item);
         }
+        peekable
+    }
+
+    /// Create a new [Peekable] over elements that don't implement [Clone].
+    ///
+    /// Every element is wrapped into a [Cloneable] that uses `clone` to
+    /// clone the element, e.g. into the backward peek buffer.
+    /// Therefore, the elements arrive as `Cloneable<T>` instead of `T`.
+    /// Use [Cloneable::get] or [Cloneable::into_inner] to access the element.
+    /// This is useful for elements like `Box<dyn Trait>`.
+    /// Iterators over [Clone] elements should use [Self::new] instead.
+    #[inline]
+    pub fn new_cloneable<II>(
+        iter: II,
+        clone: fn(&II::Item) -> II::Item,
+    ) -> Peekable<CloneableIter<II::IntoIter>, BWD_SIZE, FWD_SIZE>
+    where
+        II: IntoIterator,
+    {
+        Self::new(CloneableIter {
+            iter: iter.into_iter(),
+            clone,
+        })
     }
 
     /// Peek the previous element that has last been returned from [Self::next].
@@ -140,7 +370,11 @@ where
     /// - the backward peek buffer is too small to hold `i + 1` elements.
     #[inline]
     pub fn peek_bwd_nth(&mut self, i: usize) -> Option<&I::Item> {
-        self.bwd_buf.get(i)
+        if i < self.bwd_buffered() {
+            self.bwd_buf.get(i)
+        } else {
+            None
+        }
     }
 
     /// Peek the next n-th element.
@@ -149,9 +383,12 @@ where
     ///
     /// Successive peeks at the same position will return the same element.
     ///
-    /// Returns None, if the inner iterator is exhausted and there is no n-th element.
+    /// Returns None, if:
+    /// - the inner iterator is exhausted and there is no n-th element, or
+    /// - `i` is not smaller than `FWD_SIZE`.
+    ///   In this case the inner iterator is not advanced.
     pub fn peek_fwd_nth(&mut self, i: usize) -> Option<&I::Item> {
-        if i < self.fwd_buf.capacity() {
+        if i < FWD_SIZE {
             while self.fwd_buf.len() <= i {
                 self.fwd_buf.push_back(self.iter.next()?);
             }
@@ -161,6 +398,138 @@ where
         }
     }
 
+    /// Un-consume the element that has last been returned from [Self::next],
+    /// if `pred` returns true for it.
+    ///
+    /// The element is moved from the backward peek buffer into the forward peek buffer,
+    /// so that the next call to [Self::next] returns it again.
+    ///
+    /// Returns true, if the element has been un-consumed.
+    ///
+    /// Returns false, if:
+    /// - there is no element in the backward peek buffer, or
+    /// - the element has been seeded with [Self::with_bwd_history], or
+    /// - a newer element has been dropped from the history with [Self::pop_bwd], or
+    /// - backward tracking is disabled with [Self::set_track_bwd], or
+    /// - `pred` returned false, or
+    /// - the forward peek buffer is full.
+    pub fn rewind_one_if<F>(&mut self, pred: F) -> bool
+    where
+        F: FnOnce(&I::Item) -> bool,
+    {
+        if self.fwd_buf.is_full() || self.rewindable() == 0 || !self.track_bwd {
+            return false;
+        }
+        match self.bwd_buf.front() {
+            Some(item) if pred(item) => {
+                self.rewindable = self.rewindable() - 1;
+                if let Some(item) = self.bwd_buf.pop_front() {
+                    self.fwd_buf.push_front(item);
+                    self.pos -= 1;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Advance the iterator, like [Self::next],
+    /// and also return the element that has been evicted from the backward peek buffer.
+    ///
+    /// Returns a tuple of:
+    /// - the consumed element (same as the return value of [Self::next]), and
+    /// - the oldest element that has been dropped out of the full backward peek buffer
+    ///   during this step, or None, if the backward peek buffer had room left.
+    ///
+    /// The history cleared by consuming a record sentinel is not reported.
+    /// See [Self::set_record_sentinel].
+    pub fn next_checked_bwd(&mut self) -> (Option<I::Item>, Option<I::Item>) {
+        let item = self.pull();
+        let evicted = match &item {
+            Some(item) => self.record(item),
+            None => None,
+        };
+        (item, evicted)
+    }
+
+    /// Take the next element from the forward peek buffer or from the inner iterator.
+    ///
+    /// This does not record the element in the backward peek buffer.
+    #[inline]
+    fn pull(&mut self) -> Option<I::Item> {
+        match self.fwd_buf.pop_front() {
+            Some(item) => {
+                self.refill_deep();
+                Some(item)
+            }
+            None => self.iter.next(),
+        }
+    }
+
+    /// Move the first element that has been borrowed by [Self::peek_fwd_nth_deep]
+    /// from the backward peek buffer into the forward peek buffer.
+    fn refill_deep(&mut self) {
+        if self.deep > 0 && !self.fwd_buf.is_full() {
+            if let Some(item) = self.bwd_buf.remove(self.bwd_buffered()) {
+                self.fwd_buf.push_back(item);
+            }
+            self.deep -= 1;
+        }
+    }
+
+    /// Account a consumed element and record it in the backward peek buffer.
+    ///
+    /// Returns the element that has been evicted from the backward peek buffer.
+    #[inline]
+    fn record(&mut self, item: &I::Item) -> Option<I::Item> {
+        self.pos += 1;
+        if !self.track_bwd {
+            return None;
+        }
+        if let Some((sentinel, eq)) = &self.sentinel {
+            if eq(item, sentinel) {
+                for _ in 0..self.bwd_buffered() {
+                    self.bwd_buf.pop_front();
+                }
+                return None;
+            }
+        }
+        self.rewindable = (self.rewindable + 1).min(BWD_SIZE);
+        self.bwd_buf.push_front(item.clone())
+    }
+
+    /// Get the number of the newest elements in the backward peek buffer,
+    /// that can be un-consumed without skipping over a forgotten element.
+    #[inline]
+    fn rewindable(&self) -> usize {
+        self.rewindable.min(self.bwd_buffered())
+    }
+
+    /// Convert this iterator into an iterator that yields each element
+    /// together with its [Position] in the stream.
+    ///
+    /// The first yielded element is classified as [Position::First]
+    /// and the last one is classified as [Position::Last].
+    /// If there is only one element, it is classified as [Position::Only].
+    ///
+    /// The end of the stream is detected with a forward peek.
+    /// Therefore, `FWD_SIZE` must be at least 1.
+    pub fn with_position(mut self) -> impl Iterator<Item = (Position, I::Item)> {
+        let mut first = true;
+        core::iter::from_fn(move || {
+            let item = self.next()?;
+            let last = self.peek_fwd().is_none();
+            let pos = match (first, last) {
+                (true, true) => Position::Only,
+                (true, false) => Position::First,
+                (false, true) => Position::Last,
+                (false, false) => Position::Middle,
+            };
+            first = false;
+            Some((pos, item))
+        })
+    }
+
     /// Alias for [Self::peek_fwd_nth].
     ///
     /// This is function just here to make this library more compatible with
@@ -169,115 +538,4842 @@ where
     pub fn peek_nth(&mut self, i: usize) -> Option<&I::Item> {
         self.peek_fwd_nth(i)
     }
-}
 
-impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Iterator for Peekable<I, BWD_SIZE, FWD_SIZE>
-where
-    I: Iterator,
-    I::Item: Clone,
-{
-    type Item = I::Item;
+    /// Convert this iterator into an iterator that collapses
+    /// consecutive equal elements into one element.
+    ///
+    /// Duplicates are detected with a forward peek.
+    /// Therefore, `FWD_SIZE` must be at least 1.
+    ///
+    /// Skipped duplicates are not recorded in the backward peek buffer.
+    pub fn dedup(mut self) -> impl Iterator<Item = I::Item>
+    where
+        I::Item: PartialEq,
+    {
+        core::iter::from_fn(move || {
+            let item = self.next()?;
+            while self.peek_fwd() == Some(&item) {
+                self.fwd_buf.pop_front();
+                self.refill_deep();
+            }
+            Some(item)
+        })
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let item = self.fwd_buf.pop_front().or_else(|| self.iter.next());
-        if let Some(item) = &item {
-            self.bwd_buf.push_front(item.clone());
-        }
-        item
+    /// Convert this iterator into an iterator that merges adjacent elements.
+    ///
+    /// The closure `f` is called with the current element and the following element.
+    /// - If `f` returns `Ok(merged)`, then `merged` becomes the current element
+    ///   and it is tried to merge it with the next following element.
+    /// - If `f` returns `Err((a, b))`, then `a` is yielded and `b` becomes the current element.
+    ///
+    /// The following element is obtained with a forward peek.
+    /// Therefore, `FWD_SIZE` must be at least 1.
+    pub fn coalesce<F>(mut self, mut f: F) -> impl Iterator<Item = I::Item>
+    where
+        F: FnMut(I::Item, I::Item) -> Result<I::Item, (I::Item, I::Item)>,
+    {
+        core::iter::from_fn(move || {
+            let mut cur = self.next()?;
+            while self.peek_fwd().is_some() {
+                if let Some(next) = self.fwd_buf.pop_front() {
+                    match f(cur, next) {
+                        Ok(merged) => {
+                            cur = merged;
+                            self.refill_deep();
+                        }
+                        Err((prev, next)) => {
+                            self.fwd_buf.push_front(next);
+                            return Some(prev);
+                        }
+                    }
+                }
+            }
+            Some(cur)
+        })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use core::slice::Iter;
+    /// Return an iterator that consumes and yields elements from this iterator,
+    /// as long as `pred` returns true for them.
+    ///
+    /// Unlike [Iterator::take_while], the first element for which `pred` returns false
+    /// is not consumed. It remains available to [Self::peek_fwd] and [Self::next].
+    ///
+    /// The decision is made with a forward peek.
+    /// Therefore, `FWD_SIZE` must be at least 1.
+    pub fn take_while_peek<'a, F>(&'a mut self, mut pred: F) -> impl Iterator<Item = I::Item> + 'a
+    where
+        F: FnMut(&I::Item) -> bool + 'a,
+    {
+        let mut done = false;
+        core::iter::from_fn(move || {
+            if done || !pred(self.peek_fwd()?) {
+                done = true;
+                return None;
+            }
+            self.next()
+        })
+    }
 
-    #[test]
-    fn test_next() {
-        let a = [1, 2, 3];
-        let mut it = Peekable::<Iter<i32>, 4, 4>::new(&a);
+    /// Get the number of elements currently held in the backward peek buffer.
+    ///
+    /// This is the number of elements that can be peeked with [Self::peek_bwd_nth].
+    #[inline]
+    pub fn bwd_buffered(&self) -> usize {
+        self.bwd_buf.len() - self.deep
+    }
 
-        assert_eq!(it.next(), Some(&1));
-        assert_eq!(it.next(), Some(&2));
-        assert_eq!(it.next(), Some(&3));
-        assert_eq!(it.next(), None);
-        assert_eq!(it.next(), None);
-        assert_eq!(it.next(), None);
+    /// Get the number of elements currently held in the forward peek buffer.
+    ///
+    /// This does not pull any elements from the inner iterator.
+    #[inline]
+    pub fn fwd_buffered(&self) -> usize {
+        self.fwd_buf.len()
     }
 
-    #[test]
-    fn test_peek_fwd() {
-        let a = [1, 2, 3];
-        let mut it = Peekable::<Iter<i32>, 4, 4>::new(&a);
+    /// Get a [Cursor] for navigating within the currently buffered elements.
+    ///
+    /// The cursor starts at offset 0, which is the next element.
+    ///
+    /// The cursor does not consume elements and does not pull elements
+    /// from the inner iterator.
+    #[inline]
+    pub fn cursor(&self) -> Cursor<'_, I, BWD_SIZE, FWD_SIZE> {
+        Cursor {
+            peekable: self,
+            offset: 0,
+        }
+    }
 
-        assert_eq!(it.peek(), Some(&&1));
-        assert_eq!(it.peek(), Some(&&1));
-        assert_eq!(it.peek_fwd(), Some(&&1));
-        assert_eq!(it.peek_fwd_nth(0), Some(&&1));
-        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
-        assert_eq!(it.peek_fwd_nth(2), Some(&&3));
-        assert_eq!(it.peek_fwd_nth(3), None);
-        assert_eq!(it.peek_fwd_nth(4), None);
+    /// Peek the next element, like [Self::peek_fwd],
+    /// but return a [PeekResult] instead of an [Option].
+    ///
+    /// Returns [PeekResult::Eof], if the inner iterator is exhausted.
+    #[inline]
+    pub fn peek_fwd_eof(&mut self) -> PeekResult<&I::Item> {
+        self.peek_fwd_nth_eof(0)
+    }
 
-        assert_eq!(it.next(), Some(&1));
-        assert_eq!(it.peek(), Some(&&2));
-        assert_eq!(it.peek_fwd_nth(1), Some(&&3));
+    /// Peek the next n-th element, like [Self::peek_fwd_nth],
+    /// but return a [PeekResult] instead of an [Option].
+    ///
+    /// Returns:
+    /// - [PeekResult::OverCapacity], if `i` is not smaller than `FWD_SIZE`, or
+    /// - [PeekResult::Eof], if the inner iterator is exhausted and there is no n-th element.
+    pub fn peek_fwd_nth_eof(&mut self, i: usize) -> PeekResult<&I::Item> {
+        if i >= FWD_SIZE {
+            return PeekResult::OverCapacity;
+        }
+        match self.peek_fwd_nth(i) {
+            Some(item) => PeekResult::Item(item),
+            None => PeekResult::Eof,
+        }
     }
 
-    #[test]
-    fn test_peek_fwd_lim() {
-        let a = [1, 2, 3, 4, 5, 6, 7, 8];
-        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+    /// Consume `n` elements and then peek the next element.
+    ///
+    /// The consumed elements are recorded in the backward peek buffer,
+    /// as if [Self::next] had been called `n` times.
+    ///
+    /// Returns None, if the inner iterator is exhausted
+    /// before or directly after consuming `n` elements.
+    pub fn advance_and_peek(&mut self, n: usize) -> Option<&I::Item> {
+        for _ in 0..n {
+            self.next()?;
+        }
+        self.peek_fwd()
+    }
 
-        assert_eq!(it.peek_fwd_nth(0), Some(&&1));
-        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
-        assert_eq!(it.peek_fwd_nth(2), Some(&&3));
-        assert_eq!(it.peek_fwd_nth(3), Some(&&4));
-        assert_eq!(it.peek_fwd_nth(4), None);
-        assert_eq!(it.peek_fwd_nth(5), None);
+    /// Append a second source to this iterator.
+    ///
+    /// Returns a new [Peekable] over the concatenation of the remaining elements
+    /// of this iterator and the elements of `other`.
+    ///
+    /// Elements that are already held in the forward peek buffer are preserved
+    /// and are returned before the remaining elements.
+    /// The backward peek buffer is preserved as well.
+    pub fn chain<J>(self, other: J) -> Peekable<Chain<Fuse<I>, J::IntoIter>, BWD_SIZE, FWD_SIZE>
+    where
+        J: IntoIterator<Item = I::Item>,
+    {
+        Peekable {
+            iter: self.iter.chain(other).fuse(),
+            bwd_buf: self.bwd_buf,
+            fwd_buf: self.fwd_buf,
+            pos: self.pos,
+            track_bwd: self.track_bwd,
+            deep: self.deep,
+            consume_limit: self.consume_limit,
+            sentinel: self.sentinel,
+            rewindable: self.rewindable,
+        }
+    }
 
-        assert_eq!(it.next(), Some(&1));
-        assert_eq!(it.peek_fwd_nth(0), Some(&&2));
-        assert_eq!(it.peek_fwd_nth(1), Some(&&3));
-        assert_eq!(it.peek_fwd_nth(2), Some(&&4));
-        assert_eq!(it.peek_fwd_nth(3), Some(&&5));
-        assert_eq!(it.peek_fwd_nth(4), None);
-        assert_eq!(it.peek_fwd_nth(5), None);
+    /// Peek the next n-th element, like [Self::peek_fwd_nth],
+    /// and also report whether it was already held in the forward peek buffer.
+    ///
+    /// Returns a tuple of:
+    /// - true, if the element was already buffered, or false, if it had to be
+    ///   pulled from the inner iterator during this call, and
+    /// - the peeked element.
+    pub fn fwd_buffered_and_fresh(&mut self, i: usize) -> (bool, Option<&I::Item>) {
+        let buffered = i < self.fwd_buffered();
+        (buffered, self.peek_fwd_nth(i))
     }
 
-    #[test]
-    fn test_peek_bwd() {
-        let a = [1, 2, 3];
-        let mut it = Peekable::<Iter<i32>, 4, 4>::new(&a);
+    /// Un-consume the element that has last been returned from [Self::next].
+    ///
+    /// The element is moved from the backward peek buffer into the forward peek buffer,
+    /// so that the next call to [Self::next] returns it again.
+    ///
+    /// Returns false, if there is no element in the backward peek buffer
+    /// or if the forward peek buffer is full.
+    #[inline]
+    pub fn step_back(&mut self) -> bool {
+        self.rewind_one_if(|_| true)
+    }
 
-        assert_eq!(it.peek_bwd(), None);
-        assert_eq!(it.peek_bwd_nth(0), None);
-        assert_eq!(it.peek_bwd_nth(1), None);
+    /// Un-consume up to `n` elements, like calling [Self::step_back] `n` times.
+    ///
+    /// Returns the number of elements that have actually been un-consumed.
+    /// See [Self::max_rewind].
+    pub fn step_back_by(&mut self, n: usize) -> usize {
+        let mut count = 0;
+        while count < n && self.step_back() {
+            count += 1;
+        }
+        count
+    }
 
-        assert_eq!(it.next(), Some(&1));
-        assert_eq!(it.peek_bwd(), Some(&&1));
-        assert_eq!(it.peek_bwd_nth(1), None);
-        assert_eq!(it.peek_bwd_nth(2), None);
+    /// Un-consume elements, until [Self::stream_pos] is `position`.
+    ///
+    /// Nothing is un-consumed, if `position` is after the current position
+    /// or if it is out of reach of [Self::max_rewind].
+    ///
+    /// Returns true, if the iterator is at `position` now.
+    pub fn rewind_to(&mut self, position: usize) -> bool {
+        match self.pos.checked_sub(position) {
+            Some(n) if n <= self.max_rewind() => self.step_back_by(n) == n,
+            _ => false,
+        }
+    }
 
-        assert_eq!(it.next(), Some(&2));
-        assert_eq!(it.peek_bwd(), Some(&&2));
-        assert_eq!(it.peek_bwd_nth(1), Some(&&1));
-        assert_eq!(it.peek_bwd_nth(2), None);
+    /// Get the maximum number of elements that can currently be un-consumed
+    /// with [Self::step_back_by].
+    ///
+    /// This is limited by the number of consumed elements in the backward peek buffer,
+    /// that have not been forgotten with [Self::pop_bwd] in between,
+    /// and by the free space in the forward peek buffer.
+    /// It is 0, while backward tracking is disabled with [Self::set_track_bwd].
+    #[inline]
+    pub fn max_rewind(&self) -> usize {
+        if !self.track_bwd {
+            return 0;
+        }
+        self.rewindable().min(FWD_SIZE - self.fwd_buffered())
     }
 
-    #[test]
-    fn test_peek_bwd_lim() {
-        let a = [1, 2, 3, 4, 5, 6, 7, 8];
-        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+    /// Peek the next n-th element, like [Self::peek_fwd_nth],
+    /// but pull at most `max_pulls` elements from the inner iterator.
+    ///
+    /// Returns None without pulling any element from the inner iterator,
+    /// if reaching the n-th element would require more than `max_pulls` pulls.
+    pub fn peek_fwd_nth_budget(&mut self, i: usize, max_pulls: usize) -> Option<&I::Item> {
+        if i >= FWD_SIZE || (i + 1).saturating_sub(self.fwd_buffered()) > max_pulls {
+            None
+        } else {
+            self.peek_fwd_nth(i)
+        }
+    }
 
-        assert_eq!(it.next(), Some(&1));
-        assert_eq!(it.next(), Some(&2));
-        assert_eq!(it.next(), Some(&3));
-        assert_eq!(it.peek_bwd_nth(0), Some(&&3));
-        assert_eq!(it.peek_bwd_nth(1), Some(&&2));
-        assert_eq!(it.peek_bwd_nth(2), None);
+    /// Wrap an iterator into a new [Hashing] peekable,
+    /// that feeds every consumed element into `hasher`.
+    ///
+    /// The [Iterator::Item]s must implement [Clone] and [Hash].
+    #[inline]
+    pub fn with_hasher<II, H>(iter: II, hasher: H) -> Hashing<II::IntoIter, H, BWD_SIZE, FWD_SIZE>
+    where
+        II: IntoIterator,
+        II::Item: Clone + Hash,
+        H: Hasher,
+    {
+        Hashing {
+            peekable: Peekable::<II::IntoIter, BWD_SIZE, FWD_SIZE>::new(iter),
+            hasher,
+        }
+    }
+
+    /// Convert this iterator of iterables into a [Peekable] over the flattened elements.
+    ///
+    /// The new [Peekable] peeks forward and backward over the inner elements,
+    /// across the boundaries of the outer elements.
+    ///
+    /// Outer elements that are held in the forward peek buffer are flattened first,
+    /// so no element is lost.
+    /// The backward peek buffer holds outer elements and is not carried over.
+    /// The backward tracking setting of [Self::set_track_bwd] is carried over.
+    pub fn flatten_peekable(
+        self,
+    ) -> Peekable<Flatten<Remaining<I, BWD_SIZE, FWD_SIZE>>, BWD_SIZE, FWD_SIZE>
+    where
+        I::Item: IntoIterator,
+        <I::Item as IntoIterator>::Item: Clone,
+    {
+        let track_bwd = self.track_bwd;
+        let consume_limit = self.consume_limit;
+        Peekable {
+            iter: Remaining { peekable: self }.flatten().fuse(),
+            bwd_buf: ArrayDeque::new(),
+            fwd_buf: ArrayDeque::new(),
+            pos: 0,
+            track_bwd,
+            deep: 0,
+            consume_limit,
+            sentinel: None,
+            rewindable: 0,
+        }
+    }
+
+    /// Compare the next `len` elements of this iterator and of `other`.
+    ///
+    /// `len` is clamped to the smaller one of both `FWD_SIZE`s.
+    /// This fills both forward peek buffers, but does not consume elements.
+    ///
+    /// Returns true, if all compared elements are equal.
+    /// If both streams end at the same position within the window,
+    /// then the windows are considered equal.
+    /// If only one of the streams ends within the window, they are not equal.
+    pub fn fwd_window_eq<J, const OTHER_BWD_SIZE: usize, const OTHER_FWD_SIZE: usize>(
+        &mut self,
+        other: &mut Peekable<J, OTHER_BWD_SIZE, OTHER_FWD_SIZE>,
+        len: usize,
+    ) -> bool
+    where
+        J: Iterator,
+        J::Item: Clone,
+        I::Item: PartialEq<J::Item>,
+    {
+        let len = len.min(FWD_SIZE).min(OTHER_FWD_SIZE);
+        for i in 0..len {
+            match (self.peek_fwd_nth(i), other.peek_fwd_nth(i)) {
+                (Some(a), Some(b)) if a == b => (),
+                (None, None) => break,
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Consume up to `out.len()` elements and move them into `out`.
+    ///
+    /// Elements from the forward peek buffer are consumed first,
+    /// then elements are pulled from the inner iterator.
+    /// The consumed elements are recorded in the backward peek buffer.
+    ///
+    /// Returns the number of elements that have been written to the start of `out`.
+    pub fn collect_fwd_into(&mut self, out: &mut [I::Item]) -> usize {
+        let mut count = 0;
+        for slot in out.iter_mut() {
+            match self.next() {
+                Some(item) => *slot = item,
+                None => break,
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Fill the forward peek buffer from the inner iterator.
+    ///
+    /// This pulls elements until the forward peek buffer holds `FWD_SIZE` elements
+    /// or until the inner iterator is exhausted.
+    ///
+    /// Returns the number of elements in the forward peek buffer.
+    pub fn fill_fwd(&mut self) -> usize {
+        while !self.fwd_buf.is_full() {
+            match self.iter.next() {
+                Some(item) => {
+                    self.fwd_buf.push_back(item);
+                }
+                None => break,
+            }
+        }
+        self.fwd_buffered()
+    }
+
+    /// Search the forward peek window for the first element for which `f` returns `Some`.
+    ///
+    /// The forward peek buffer is filled up to `FWD_SIZE` elements.
+    /// This does not consume elements.
+    ///
+    /// Returns the look-ahead index of the found element together with the value returned by `f`.
+    pub fn fwd_find_map<B, F>(&mut self, mut f: F) -> Option<(usize, B)>
+    where
+        F: FnMut(&I::Item) -> Option<B>,
+    {
+        self.fill_fwd();
+        self.fwd_buf
+            .iter()
+            .enumerate()
+            .find_map(|(i, item)| f(item).map(|b| (i, b)))
+    }
+
+    /// Advance both this iterator and `other` by one element
+    /// and return both consumed elements as a pair.
+    ///
+    /// Returns None and consumes nothing, if either of the iterators is exhausted.
+    /// Both iterators keep their peek buffers.
+    pub fn next_zip<J, const OTHER_BWD_SIZE: usize, const OTHER_FWD_SIZE: usize>(
+        &mut self,
+        other: &mut Peekable<J, OTHER_BWD_SIZE, OTHER_FWD_SIZE>,
+    ) -> Option<(I::Item, J::Item)>
+    where
+        J: Iterator,
+        J::Item: Clone,
+    {
+        if self.peek_fwd().is_none() || other.peek_fwd().is_none() {
+            return None;
+        }
+        Some((self.next()?, other.next()?))
+    }
+
+    /// Check whether the backward peek buffer holds `BWD_SIZE` elements.
+    ///
+    /// This is false, until at least `BWD_SIZE` elements have been consumed.
+    #[inline]
+    pub fn bwd_is_full(&self) -> bool {
+        self.bwd_buffered() == BWD_SIZE
+    }
+
+    /// Check whether the forward peek buffer holds `FWD_SIZE` elements.
+    ///
+    /// This does not pull any elements from the inner iterator.
+    #[inline]
+    pub fn fwd_is_full(&self) -> bool {
+        self.fwd_buf.is_full()
+    }
+
+    /// Convert this iterator into a stateful adaptor, like [Iterator::scan],
+    /// that also passes the next element as look-ahead to the closure.
+    ///
+    /// The closure `f` is called with the mutable state, the current element
+    /// and the next element (or None, if the current element is the last one).
+    /// Iteration ends when `f` returns None.
+    ///
+    /// The next element is obtained with a forward peek.
+    /// Therefore, `FWD_SIZE` must be at least 1.
+    pub fn scan_la<St, B, F>(mut self, init: St, mut f: F) -> impl Iterator<Item = B>
+    where
+        F: FnMut(&mut St, I::Item, Option<&I::Item>) -> Option<B>,
+    {
+        let mut state = init;
+        let mut done = false;
+        core::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let item = self.next()?;
+            let ret = f(&mut state, item, self.peek_fwd());
+            done = ret.is_none();
+            ret
+        })
+    }
+
+    /// Convert this iterator into an iterator that skips every element
+    /// that is equal to the previously consumed element.
+    ///
+    /// The previously consumed element is obtained with a backward peek.
+    /// Therefore, `BWD_SIZE` must be at least 1.
+    ///
+    /// Unlike [Self::dedup], this also compares the first element against
+    /// the element that has last been consumed before the conversion.
+    /// Skipped duplicates are not recorded in the backward peek buffer.
+    pub fn dedup_bwd(mut self) -> impl Iterator<Item = I::Item>
+    where
+        I::Item: PartialEq,
+    {
+        core::iter::from_fn(move || loop {
+            let item = self.pull()?;
+            if self.peek_bwd() != Some(&item) {
+                self.record(&item);
+                return Some(item);
+            }
+        })
+    }
+
+    /// Get the absolute stream position.
+    ///
+    /// This is the number of elements that have been consumed with [Self::next],
+    /// minus the number of elements that have been un-consumed.
+    /// It is the absolute stream index of the next element.
+    ///
+    /// This is not named `position` to avoid a clash with [Iterator::position].
+    #[inline]
+    pub fn stream_pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Peek the next n-th element, like [Self::peek_fwd_nth],
+    /// and also return its absolute stream index.
+    ///
+    /// The absolute stream index is `stream_pos() + i`.
+    pub fn peek_fwd_nth_at(&mut self, i: usize) -> Option<(usize, &I::Item)> {
+        let pos = self.pos + i;
+        self.peek_fwd_nth(i).map(|item| (pos, item))
+    }
+
+    /// Peek the next n-th element, like [Self::peek_fwd_nth],
+    /// but return a [PeekError] on failure.
+    ///
+    /// Returns:
+    /// - [PeekError::OverCapacity], if `i` is not smaller than `FWD_SIZE`, or
+    /// - [PeekError::Exhausted], if the inner iterator is exhausted and there is no n-th element.
+    pub fn peek_fwd_nth_result(&mut self, i: usize) -> Result<&I::Item, PeekError> {
+        if i >= FWD_SIZE {
+            return Err(PeekError::OverCapacity {
+                requested: i,
+                capacity: FWD_SIZE,
+            });
+        }
+        self.peek_fwd_nth(i).ok_or(PeekError::Exhausted)
+    }
+
+    /// Enable or disable recording of consumed elements in the backward peek buffer.
+    ///
+    /// Backward tracking is enabled by default.
+    ///
+    /// While disabled, [Self::next] does not clone consumed elements
+    /// into the backward peek buffer.
+    /// The backward peek buffer keeps its old contents and becomes stale.
+    /// Stale elements can't be un-consumed. Therefore, [Self::step_back] and all
+    /// other rewinding operations fail while backward tracking is disabled.
+    ///
+    /// Re-enabling clears the backward peek buffer and starts fresh tracking.
+    pub fn set_track_bwd(&mut self, enabled: bool) {
+        if enabled && !self.track_bwd {
+            for _ in 0..self.bwd_buffered() {
+                self.bwd_buf.pop_front();
+            }
+        }
+        self.track_bwd = enabled;
+    }
+
+    /// Map the next `N` elements with `f` into an array.
+    ///
+    /// The forward peek buffer is filled with `N` elements.
+    /// This does not consume elements.
+    ///
+    /// Returns None, if `N` is bigger than `FWD_SIZE`
+    /// or if there are less than `N` elements left.
+    pub fn peek_fwd_map_array<B, const N: usize, F>(&mut self, mut f: F) -> Option<[B; N]>
+    where
+        F: FnMut(&I::Item) -> B,
+    {
+        if N > FWD_SIZE || (N > 0 && self.peek_fwd_nth(N - 1).is_none()) {
+            return None;
+        }
+        Some(core::array::from_fn(|i| f(&self.fwd_buf[i])))
+    }
+
+    /// Fill the forward peek buffer, like [Self::fill_fwd],
+    /// and report whether the buffer became full.
+    ///
+    /// Returns true, if the forward peek buffer is full.
+    /// In this case more elements may follow.
+    /// Returns false, if the stream ends within the forward peek buffer capacity.
+    ///
+    /// This does not pull more than `FWD_SIZE` elements into the buffer.
+    pub fn prefetch_saturating(&mut self) -> bool {
+        self.fill_fwd();
+        self.fwd_is_full()
+    }
+
+    /// Check whether the most recently consumed elements are equal to `pattern`.
+    ///
+    /// The ordering is the same as in [Self::peek_bwd_nth]:
+    /// `pattern[0]` is compared to the element from the last [Self::next] call,
+    /// `pattern[1]` to the element from the previous to last call, etc...
+    ///
+    /// Returns false, if `pattern` is longer than the number of elements
+    /// in the backward peek buffer.
+    pub fn matches_bwd<T>(&self, pattern: &[T]) -> bool
+    where
+        I::Item: PartialEq<T>,
+    {
+        pattern.len() <= self.bwd_buffered()
+            && self.bwd_buf.iter().zip(pattern).all(|(item, p)| item == p)
+    }
+
+    /// Remove and return the element that has last been returned from [Self::next]
+    /// from the backward peek buffer.
+    ///
+    /// The element is forgotten from the history.
+    /// Unlike [Self::step_back], it is not re-queued into the forward peek buffer
+    /// and the stream position does not change.
+    /// The remaining older elements can't be un-consumed anymore,
+    /// because rewinding them would skip the forgotten element.
+    ///
+    /// Returns None, if the backward peek buffer is empty.
+    #[inline]
+    pub fn pop_bwd(&mut self) -> Option<I::Item> {
+        if self.bwd_buffered() > 0 {
+            self.rewindable = 0;
+            self.bwd_buf.pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// Convert this iterator into a [Peekable] over the elements
+    /// produced by [Iterator::flat_map].
+    ///
+    /// The new [Peekable] peeks forward and backward over the produced elements,
+    /// across the boundaries of the expansions.
+    ///
+    /// Unexpanded elements that are held in the forward peek buffer are expanded first,
+    /// so no element is lost.
+    /// The backward peek buffer holds unexpanded elements and is not carried over.
+    /// The backward tracking setting of [Self::set_track_bwd] is carried over.
+    pub fn flat_map_peek<U, F>(
+        self,
+        f: F,
+    ) -> Peekable<FlatMap<Remaining<I, BWD_SIZE, FWD_SIZE>, U, F>, BWD_SIZE, FWD_SIZE>
+    where
+        U: IntoIterator,
+        U::Item: Clone,
+        F: FnMut(I::Item) -> U,
+    {
+        let track_bwd = self.track_bwd;
+        let consume_limit = self.consume_limit;
+        Peekable {
+            iter: Remaining { peekable: self }.flat_map(f).fuse(),
+            bwd_buf: ArrayDeque::new(),
+            fwd_buf: ArrayDeque::new(),
+            pos: 0,
+            track_bwd,
+            deep: 0,
+            consume_limit,
+            sentinel: None,
+            rewindable: 0,
+        }
+    }
+
+    /// Assert the internal consistency of the buffers.
+    ///
+    /// This panics, if an internal invariant is violated.
+    /// It is only available in tests or with the `testing` feature enabled.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn debug_invariants(&self) {
+        // Borrowed backward slots only hold look-ahead beyond a full forward peek buffer.
+        assert!(self.deep <= self.bwd_buf.len());
+        assert!(self.deep == 0 || self.fwd_buf.is_full());
+        // Only consumed elements can be un-consumed.
+        assert!(self.rewindable() <= self.pos);
+        // The history never reaches across a record sentinel.
+        if let Some((sentinel, eq)) = &self.sentinel {
+            assert!(self.peek_bwd_iter().all(|item| !eq(item, sentinel)));
+        }
+    }
+
+    /// Capture the current state as a [PeekCursor] token.
+    #[inline]
+    pub fn peek_cursor(&self) -> PeekCursor {
+        PeekCursor {
+            position: self.stream_pos(),
+            bwd_buffered: self.bwd_buffered(),
+        }
+    }
+
+    /// Consume and return the next element, if `pred` returns true.
+    ///
+    /// `pred` is called with the element that has last been returned from [Self::next]
+    /// (or None, if there is none) and with the next element.
+    /// The previous element is obtained with a backward peek.
+    /// Therefore, `BWD_SIZE` should be at least 1.
+    ///
+    /// Returns None and consumes nothing, if `pred` returns false
+    /// or if the inner iterator is exhausted.
+    pub fn next_if_prev<F>(&mut self, mut pred: F) -> Option<I::Item>
+    where
+        F: FnMut(Option<&I::Item>, &I::Item) -> bool,
+    {
+        self.peek_fwd()?;
+        let next = self.fwd_buf.front()?;
+        let prev = if self.bwd_buffered() > 0 {
+            self.bwd_buf.front()
+        } else {
+            None
+        };
+        if pred(prev, next) {
+            self.next()
+        } else {
+            None
+        }
+    }
+
+    /// Limit this iterator to at most `n` further elements.
+    ///
+    /// Returns a new [Peekable] that yields at most `n` elements
+    /// and that peeks within these `n` elements only.
+    ///
+    /// Elements that are already held in the forward peek buffer
+    /// count towards `n`.
+    /// Buffered elements beyond `n` are dropped.
+    /// The backward peek buffer is preserved.
+    pub fn limit(mut self, n: usize) -> Peekable<Take<Fuse<I>>, BWD_SIZE, FWD_SIZE> {
+        while self.deep > 0 && self.fwd_buffered() + self.deep > n {
+            self.bwd_buf.pop_back();
+            self.deep -= 1;
+        }
+        while self.fwd_buffered() > n {
+            self.fwd_buf.pop_back();
+        }
+        let remaining = n - self.fwd_buffered() - self.deep;
+        Peekable {
+            iter: self.iter.take(remaining).fuse(),
+            bwd_buf: self.bwd_buf,
+            fwd_buf: self.fwd_buf,
+            pos: self.pos,
+            track_bwd: self.track_bwd,
+            deep: self.deep,
+            consume_limit: self.consume_limit,
+            sentinel: self.sentinel,
+            rewindable: self.rewindable,
+        }
+    }
+
+    /// Consume and return the next element, if it is equal to `value`.
+    ///
+    /// Returns:
+    /// - [PeekError::Exhausted], if the inner iterator is exhausted, or
+    /// - [PeekError::Mismatch], if the next element is not equal to `value`.
+    ///   The element is not consumed and it remains available for peeking.
+    pub fn expect<T>(&mut self, value: &T) -> Result<I::Item, PeekError>
+    where
+        I::Item: PartialEq<T>,
+    {
+        match self.peek_fwd() {
+            None => Err(PeekError::Exhausted),
+            Some(item) if item != value => Err(PeekError::Mismatch { position: self.pos }),
+            Some(_) => self.next().ok_or(PeekError::Exhausted),
+        }
+    }
+
+    /// Start a [Transaction] on this iterator.
+    ///
+    /// Elements consumed through the returned guard are rolled back,
+    /// unless [Transaction::commit] is called.
+    #[inline]
+    pub fn transaction(&mut self) -> Transaction<'_, I, BWD_SIZE, FWD_SIZE> {
+        Transaction {
+            start: self.pos,
+            peekable: self,
+            done: false,
+        }
+    }
+
+    /// Peek the next n-th element, like [Self::peek_fwd_nth],
+    /// and also return the number of elements pulled from the inner iterator.
+    ///
+    /// The returned count includes a final pull that found the inner iterator exhausted.
+    pub fn peek_fwd_nth_counting(&mut self, i: usize) -> (Option<&I::Item>, usize) {
+        let mut pulls = 0;
+        if i < FWD_SIZE {
+            while self.fwd_buf.len() <= i {
+                pulls += 1;
+                match self.iter.next() {
+                    Some(item) => {
+                        self.fwd_buf.push_back(item);
+                    }
+                    None => return (None, pulls),
+                }
+            }
+            (Some(&self.fwd_buf[i]), pulls)
+        } else {
+            (None, pulls)
+        }
+    }
+
+    /// Convert this iterator into a [core::iter::Peekable].
+    ///
+    /// Elements that are held in the forward peek buffer are returned first,
+    /// so no element is lost.
+    /// The backward peek buffer is dropped.
+    pub fn into_std_peekable(self) -> core::iter::Peekable<impl Iterator<Item = I::Item>> {
+        let history = self.bwd_buffered();
+        self.fwd_buf
+            .into_iter()
+            .chain(self.bwd_buf.into_iter().skip(history))
+            .chain(self.iter)
+            .peekable()
+    }
+
+    /// Get an iterator over references to the upcoming elements
+    /// up to, but not including, the first element that is equal to `sentinel`.
+    ///
+    /// The forward peek buffer is filled up to `FWD_SIZE` elements
+    /// and the view is bounded by the forward peek buffer.
+    /// If `sentinel` is not found within the buffer, then the whole buffer is returned.
+    /// This does not consume elements.
+    pub fn fwd_view_until_sentinel<'a, T>(
+        &'a mut self,
+        sentinel: T,
+    ) -> impl Iterator<Item = &'a I::Item> + 'a
+    where
+        I::Item: PartialEq<T>,
+        T: 'a,
+    {
+        self.fill_fwd();
+        self.fwd_buf
+            .iter()
+            .take_while(move |item| **item != sentinel)
+    }
+
+    /// Convert this iterator into an iterator over [Vec] chunks of `size` elements.
+    ///
+    /// The last chunk may be shorter than `size`, if there are not enough elements left.
+    /// The consumed elements are recorded in the backward peek buffer.
+    ///
+    /// This is only available with the `alloc` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `size` is 0.
+    #[cfg(feature = "alloc")]
+    pub fn chunks(mut self, size: usize) -> impl Iterator<Item = Vec<I::Item>> {
+        assert!(size != 0, "Chunk size must not be 0");
+        core::iter::from_fn(move || {
+            self.peek_fwd()?;
+            let mut chunk = Vec::with_capacity(size);
+            while chunk.len() < size {
+                match self.next() {
+                    Some(item) => chunk.push(item),
+                    None => break,
+                }
+            }
+            Some(chunk)
+        })
+    }
+
+    /// Check whether the upcoming elements are equal to `pattern`.
+    fn fwd_matches<T>(&mut self, pattern: &[T]) -> bool
+    where
+        I::Item: PartialEq<T>,
+    {
+        pattern.len() <= FWD_SIZE
+            && pattern
+                .iter()
+                .enumerate()
+                .all(|(i, p)| self.peek_fwd_nth(i).is_some_and(|item| item == p))
+    }
+
+    /// Find the longest pattern in `patterns` that matches the upcoming elements.
+    ///
+    /// Patterns longer than `FWD_SIZE` never match.
+    /// If several patterns of the same length match, the first one wins.
+    /// This does not consume elements.
+    ///
+    /// Returns the index of the matching pattern in `patterns`,
+    /// or None, if no pattern matches.
+    pub fn longest_fwd_prefix_match<T>(&mut self, patterns: &[&[T]]) -> Option<usize>
+    where
+        I::Item: PartialEq<T>,
+    {
+        let mut best: Option<(usize, usize)> = None;
+        for (idx, pattern) in patterns.iter().enumerate() {
+            let longer = match best {
+                Some((_, len)) => pattern.len() > len,
+                None => true,
+            };
+            if longer && self.fwd_matches(pattern) {
+                best = Some((idx, pattern.len()));
+            }
+        }
+        best.map(|(idx, _)| idx)
+    }
+
+    /// Find the longest pattern in `patterns` that matches the upcoming elements,
+    /// like [Self::longest_fwd_prefix_match], and consume the matched elements.
+    ///
+    /// The consumed elements are recorded in the backward peek buffer.
+    /// Nothing is consumed, if no pattern matches.
+    ///
+    /// Returns the index of the matching pattern in `patterns`,
+    /// or None, if no pattern matches.
+    pub fn consume_longest_fwd_prefix<T>(&mut self, patterns: &[&[T]]) -> Option<usize>
+    where
+        I::Item: PartialEq<T>,
+    {
+        let idx = self.longest_fwd_prefix_match(patterns)?;
+        for _ in 0..patterns[idx].len() {
+            self.next();
+        }
+        Some(idx)
+    }
+
+    /// Get a lazy iterator that yields `f` applied to each upcoming element.
+    ///
+    /// The forward peek buffer is filled lazily, one element per yielded value,
+    /// and the iterator ends at the end of the forward peek buffer capacity.
+    /// This does not consume elements.
+    pub fn peek_fwd_scan<'a, B, F>(&'a mut self, mut f: F) -> impl Iterator<Item = B> + 'a
+    where
+        F: FnMut(&I::Item) -> B + 'a,
+    {
+        let mut i = 0;
+        core::iter::from_fn(move || {
+            let item = self.peek_fwd_nth(i)?;
+            i += 1;
+            Some(f(item))
+        })
+    }
+
+    /// Get a [SubScanner] for handing this iterator to a sub-routine.
+    ///
+    /// The sub-scanner shares the buffers and the position with this iterator,
+    /// but it only exposes peeking and consumption.
+    #[inline]
+    pub fn sub(&mut self) -> SubScanner<'_, I, BWD_SIZE, FWD_SIZE> {
+        SubScanner { peekable: self }
+    }
+
+    /// Consume a maximal run of elements that are the `same` as the first element.
+    ///
+    /// The first element is always consumed, if there is one.
+    /// Following elements are consumed as long as `same(first, element)` returns true.
+    /// The consumed elements are recorded in the backward peek buffer.
+    ///
+    /// The run is not bounded by the buffer sizes.
+    /// The decision is made with a forward peek.
+    /// Therefore, `FWD_SIZE` must be at least 1.
+    ///
+    /// The run is cut short, if the limit set with [Self::set_consume_limit] is reached.
+    ///
+    /// Returns the length of the run.
+    pub fn consume_run<F>(&mut self, mut same: F) -> usize
+    where
+        F: FnMut(&I::Item, &I::Item) -> bool,
+    {
+        if self.consume_limit_reached(0) {
+            return 0;
+        }
+        let Some(first) = self.next() else {
+            return 0;
+        };
+        let mut count = 1;
+        while !self.consume_limit_reached(count)
+            && self.peek_fwd().is_some_and(|item| same(&first, item))
+        {
+            self.next();
+            count += 1;
+        }
+        count
+    }
+
+    /// Convert this iterator into an iterator that yields each element
+    /// together with a clone of its predecessor.
+    ///
+    /// The predecessor is None for the first element of the stream.
+    /// If elements have been consumed before the conversion, then the first
+    /// yielded element is paired with the element that has last been consumed.
+    ///
+    /// The predecessor is obtained with a backward peek.
+    /// Therefore, `BWD_SIZE` must be at least 1.
+    pub fn with_previous(mut self) -> impl Iterator<Item = (Option<I::Item>, I::Item)> {
+        core::iter::from_fn(move || {
+            let prev = self.peek_bwd().cloned();
+            let item = self.next()?;
+            Some((prev, item))
+        })
+    }
+
+    /// Let `f` decide how many of the next `n` elements to consume.
+    ///
+    /// `n` is clamped to `FWD_SIZE`.
+    /// The forward peek buffer is filled with up to `n` elements
+    /// and `f` is called with references to them.
+    /// The slice is shorter than `n`, if there are not enough elements left.
+    ///
+    /// If `f` returns `Some(count)`, then `count` elements are consumed.
+    /// `count` is clamped to the length of the slice.
+    /// If `f` returns None, then nothing is consumed.
+    ///
+    /// Returns the number of consumed elements.
+    pub fn commit_window_if<F>(&mut self, n: usize, f: F) -> usize
+    where
+        F: FnOnce(&[&I::Item]) -> Option<usize>,
+    {
+        let n = n.min(FWD_SIZE);
+        let avail = if n == 0 {
+            0
+        } else {
+            self.peek_fwd_nth(n - 1);
+            self.fwd_buffered().min(n)
+        };
+        let count = if avail == 0 {
+            f(&[])
+        } else {
+            // Pad the unused tail with the last element to build the array without allocation.
+            let refs: [&I::Item; FWD_SIZE] =
+                core::array::from_fn(|i| &self.fwd_buf[i.min(avail - 1)]);
+            f(&refs[..avail])
+        };
+        let count = count.unwrap_or(0).min(avail);
+        for _ in 0..count {
+            self.next();
+        }
+        count
+    }
+
+    /// Wrap this iterator into an [Observed] peekable,
+    /// that calls `f` exactly once for every consumed element.
+    ///
+    /// This is like [Iterator::inspect], but the returned iterator keeps
+    /// the peek methods and the consumed elements still enter the backward peek buffer.
+    /// Peeking does not call `f`.
+    #[inline]
+    pub fn on_consume<F>(self, f: F) -> Observed<I, F, BWD_SIZE, FWD_SIZE>
+    where
+        F: FnMut(&I::Item),
+    {
+        Observed { peekable: self, f }
+    }
+
+    /// Peek the next n-th element together with the element
+    /// that has last been returned from [Self::next].
+    ///
+    /// Returns a tuple of `(previous, next_nth)`.
+    ///
+    /// Returns None, if:
+    /// - there was no call to [Self::next], yet, or
+    /// - there is no n-th element. See [Self::peek_fwd_nth].
+    pub fn peek_fwd_with_context(&mut self, i: usize) -> Option<(&I::Item, &I::Item)> {
+        self.peek_fwd_nth(i)?;
+        if self.bwd_buffered() == 0 {
+            return None;
+        }
+        Some((self.bwd_buf.front()?, &self.fwd_buf[i]))
+    }
+
+    /// No-op for API compatibility with [VecDeque](https://doc.rust-lang.org/std/collections/struct.VecDeque.html) based scanners.
+    ///
+    /// The peek buffers have a fixed size and are never reallocated.
+    #[inline]
+    pub fn shrink_to_fit(&self) {}
+
+    /// Get the number of free element slots in the forward peek buffer.
+    ///
+    /// This is `FWD_SIZE - fwd_buffered()`.
+    #[inline]
+    pub fn free_fwd_capacity(&self) -> usize {
+        FWD_SIZE - self.fwd_buffered()
+    }
+
+    /// Get the number of free element slots in the backward peek buffer.
+    ///
+    /// This is `BWD_SIZE - bwd_buffered()`,
+    /// minus the slots that are borrowed by [Self::peek_fwd_nth_deep].
+    #[inline]
+    pub fn free_bwd_capacity(&self) -> usize {
+        BWD_SIZE - self.bwd_buf.len()
+    }
+
+    /// Consume the next element and return it transformed by `f`.
+    ///
+    /// A clone of the original element is recorded in the backward peek buffer
+    /// before `f` takes ownership of it.
+    /// Therefore, backward peeks return the original element.
+    pub fn next_map<B, F>(&mut self, f: F) -> Option<B>
+    where
+        F: FnOnce(I::Item) -> B,
+    {
+        self.next().map(f)
+    }
+
+    /// Fill the forward peek buffer, like [Self::fill_fwd],
+    /// but stop after the first newly pulled element for which `pred` returns false.
+    ///
+    /// The element for which `pred` returned false is kept in the forward peek buffer.
+    /// `pred` is only called for newly pulled elements.
+    ///
+    /// Returns the number of elements in the forward peek buffer.
+    pub fn fill_fwd_while<F>(&mut self, mut pred: F) -> usize
+    where
+        F: FnMut(&I::Item) -> bool,
+    {
+        while !self.fwd_buf.is_full() {
+            match self.iter.next() {
+                Some(item) => {
+                    let more = pred(&item);
+                    self.fwd_buf.push_back(item);
+                    if !more {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        self.fwd_buffered()
+    }
+
+    /// Consume one element alternately from this iterator or from `other`.
+    ///
+    /// If `take_self_first` is true, then the element is taken from this iterator,
+    /// otherwise it is taken from `other`.
+    /// If the chosen side is exhausted, then the element is taken from the other side.
+    /// `take_self_first` is flipped on every call.
+    ///
+    /// Both iterators keep their peek buffers.
+    ///
+    /// Returns None, if both iterators are exhausted.
+    pub fn next_interleaved<J, const OTHER_BWD_SIZE: usize, const OTHER_FWD_SIZE: usize>(
+        &mut self,
+        other: &mut Peekable<J, OTHER_BWD_SIZE, OTHER_FWD_SIZE>,
+        take_self_first: &mut bool,
+    ) -> Option<I::Item>
+    where
+        J: Iterator<Item = I::Item>,
+    {
+        let item = if *take_self_first {
+            self.next().or_else(|| other.next())
+        } else {
+            other.next().or_else(|| self.next())
+        };
+        *take_self_first = !*take_self_first;
+        item
+    }
+
+    /// Check whether the forward peek window is sorted in non-decreasing order.
+    ///
+    /// The forward peek buffer is filled up to `FWD_SIZE` elements.
+    /// Adjacent elements that are not comparable (e.g. `NaN`) are treated as not sorted.
+    /// This does not consume elements.
+    pub fn fwd_is_sorted(&mut self) -> bool
+    where
+        I::Item: PartialOrd,
+    {
+        self.fill_fwd();
+        self.fwd_buf
+            .iter()
+            .zip(self.fwd_buf.iter().skip(1))
+            .all(|(a, b)| a <= b)
+    }
+
+    /// Get the number of already buffered elements at or beyond look-ahead index `i`.
+    ///
+    /// This is `fwd_buffered().saturating_sub(i)`.
+    /// This does not pull any elements from the inner iterator.
+    #[inline]
+    pub fn fwd_remaining_buffered_from(&self, i: usize) -> usize {
+        self.fwd_buffered().saturating_sub(i)
+    }
+
+    /// Peek the next n-th element, like [Self::peek_fwd_nth],
+    /// but allow `i` to exceed `FWD_SIZE` by borrowing free backward peek buffer slots.
+    ///
+    /// If the forward peek buffer is full, then up to [Self::free_bwd_capacity]
+    /// further elements are stored in unused slots of the backward peek buffer.
+    /// The borrowed slots are not visible to backward peeks.
+    /// They are handed back to the backward peek buffer one by one as elements are consumed.
+    ///
+    /// Returns None, if:
+    /// - the inner iterator is exhausted and there is no n-th element, or
+    /// - `i` is not smaller than `FWD_SIZE` plus the borrowable backward slots.
+    pub fn peek_fwd_nth_deep(&mut self, i: usize) -> Option<&I::Item> {
+        if i < FWD_SIZE {
+            return self.peek_fwd_nth(i);
+        }
+        let j = i - FWD_SIZE;
+        if j >= self.deep + self.free_bwd_capacity() || self.fill_fwd() < FWD_SIZE {
+            return None;
+        }
+        while self.deep <= j {
+            let item = self.iter.next()?;
+            self.bwd_buf.push_back(item);
+            self.deep += 1;
+        }
+        self.bwd_buf.get(self.bwd_buffered() + j)
+    }
+
+    /// Run the parser `f` on this iterator and rewind, if it fails.
+    ///
+    /// `f` may consume elements.
+    /// If `f` returns Some, then all consumed elements stay consumed.
+    /// If `f` returns None, then all elements consumed by `f` are un-consumed with [Self::step_back_by].
+    /// This is the ordered choice of PEG parsers: try alternatives one after the other.
+    ///
+    /// The rewind distance is limited by [Self::max_rewind].
+    /// If `f` failed and consumed more elements than can be un-consumed,
+    /// then nothing is un-consumed and None is returned.
+    /// The iterator is either fully rewound or left where `f` stopped.
+    /// Use [Self::stream_pos] to tell these cases apart.
+    pub fn try_parse<B, F>(&mut self, f: F) -> Option<B>
+    where
+        F: FnOnce(&mut Self) -> Option<B>,
+    {
+        let start = self.pos;
+        let ret = f(self);
+        if ret.is_none() {
+            let consumed = self.pos.saturating_sub(start);
+            if consumed <= self.max_rewind() {
+                self.step_back_by(consumed);
+            }
+        }
+        ret
+    }
+
+    /// Format up to `max` of the most recently consumed elements
+    /// from the backward peek buffer, e.g. for error messages.
+    ///
+    /// The elements are separated by spaces and ordered from oldest to newest.
+    /// If older elements have been consumed, that are not shown,
+    /// then the string is prefixed with `"... "`.
+    /// For example: `"... 17 18 19"`.
+    ///
+    /// This is only available with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn bwd_context_string(&self, max: usize) -> String
+    where
+        I::Item: core::fmt::Display,
+    {
+        use core::fmt::Write;
+
+        let shown = max.min(self.bwd_buffered());
+        let mut s = String::new();
+        if self.pos > shown {
+            s.push_str("...");
+        }
+        for item in self.bwd_buf.iter().take(shown).rev() {
+            if !s.is_empty() {
+                s.push(' ');
+            }
+            let _ = write!(s, "{item}");
+        }
+        s
+    }
+
+    /// Format up to `min(max, FWD_SIZE)` of the upcoming elements
+    /// from the forward peek buffer without consuming them, e.g. for error messages.
+    ///
+    /// The forward peek buffer is filled as needed.
+    /// The elements are separated by spaces and ordered from next to later.
+    /// If more elements may follow, that are not shown,
+    /// then the string is suffixed with `" ..."`.
+    /// For example: `"20 21 22 ..."`.
+    ///
+    /// Together with [Self::bwd_context_string] this gives the full context
+    /// around the current position.
+    ///
+    /// This is only available with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn fwd_context_string(&mut self, max: usize) -> String
+    where
+        I::Item: core::fmt::Display,
+    {
+        use core::fmt::Write;
+
+        let wanted = max.min(FWD_SIZE);
+        let mut shown = 0;
+        while shown < wanted && self.peek_fwd_nth(shown).is_some() {
+            shown += 1;
+        }
+        let more = if shown < wanted {
+            false
+        } else if shown < FWD_SIZE {
+            self.peek_fwd_nth(shown).is_some()
+        } else {
+            true
+        };
+        let mut s = String::new();
+        for item in self.fwd_buf.iter().take(shown) {
+            if !s.is_empty() {
+                s.push(' ');
+            }
+            let _ = write!(s, "{item}");
+        }
+        if more {
+            if !s.is_empty() {
+                s.push(' ');
+            }
+            s.push_str("...");
+        }
+        s
+    }
+
+    /// Convert this iterator into an iterator that yields each run of
+    /// consecutive equal elements as the element and the run length.
+    ///
+    /// This is a run-length encoding of the stream.
+    /// Run ends are detected with a forward peek.
+    /// Therefore, `FWD_SIZE` must be at least 1.
+    ///
+    /// All elements of a run are consumed and recorded in the backward peek buffer.
+    pub fn runs(mut self) -> impl Iterator<Item = (I::Item, usize)>
+    where
+        I::Item: PartialEq,
+    {
+        core::iter::from_fn(move || {
+            let item = self.next()?;
+            let mut len = 1;
+            while self.peek_fwd() == Some(&item) {
+                self.next();
+                len += 1;
+            }
+            Some((item, len))
+        })
+    }
+
+    /// Push `item` to the front of the forward peek buffer,
+    /// so that the next call to [Self::next] returns it.
+    ///
+    /// The item does not have to originate from this iterator.
+    /// The backward peek buffer and [Self::stream_pos] are not changed.
+    ///
+    /// Returns `Err(item)` and leaves the forward peek buffer unchanged,
+    /// if the forward peek buffer is full.
+    /// No buffered element is ever evicted.
+    pub fn unget(&mut self, item: I::Item) -> Result<(), I::Item> {
+        if self.fwd_buf.is_full() {
+            Err(item)
+        } else {
+            self.fwd_buf.push_front(item);
+            Ok(())
+        }
+    }
+
+    /// Get the length of the longest common prefix
+    /// of the forward peek windows of this iterator and of `other`.
+    ///
+    /// The length is clamped to the smaller one of both `FWD_SIZE`s.
+    /// This fills both forward peek buffers, but does not consume elements.
+    pub fn fwd_lcp_len<J, const OTHER_BWD_SIZE: usize, const OTHER_FWD_SIZE: usize>(
+        &mut self,
+        other: &mut Peekable<J, OTHER_BWD_SIZE, OTHER_FWD_SIZE>,
+    ) -> usize
+    where
+        J: Iterator,
+        J::Item: Clone,
+        I::Item: PartialEq<J::Item>,
+    {
+        let len = FWD_SIZE.min(OTHER_FWD_SIZE);
+        let mut i = 0;
+        while i < len {
+            match (self.peek_fwd_nth(i), other.peek_fwd_nth(i)) {
+                (Some(a), Some(b)) if a == b => i += 1,
+                _ => break,
+            }
+        }
+        i
+    }
+
+    /// Fold the state `init` over the forward peek window with `step`
+    /// and return the final state.
+    ///
+    /// This runs a state machine over the look-ahead, e.g. to decide what to do next.
+    /// The forward peek buffer is filled up to `FWD_SIZE` elements.
+    /// This does not consume elements.
+    pub fn fwd_classify<S, F>(&mut self, init: S, step: F) -> S
+    where
+        F: FnMut(S, &I::Item) -> S,
+    {
+        self.fill_fwd();
+        self.fwd_buf.iter().fold(init, step)
+    }
+
+    /// Perform one step of merging this iterator with `other`.
+    ///
+    /// The next elements of both iterators are compared with `cmp`
+    /// and the smaller one is consumed.
+    /// If both are equal, then both are consumed.
+    /// If one of the iterators is exhausted, then the element of the other one is consumed.
+    ///
+    /// Returns None, if both iterators are exhausted.
+    pub fn merge_step<J, F, const OTHER_BWD_SIZE: usize, const OTHER_FWD_SIZE: usize>(
+        &mut self,
+        other: &mut Peekable<J, OTHER_BWD_SIZE, OTHER_FWD_SIZE>,
+        mut cmp: F,
+    ) -> Option<MergeItem<I::Item, J::Item>>
+    where
+        J: Iterator,
+        J::Item: Clone,
+        F: FnMut(&I::Item, &J::Item) -> Ordering,
+    {
+        let ord = match (self.peek_fwd(), other.peek_fwd()) {
+            (Some(a), Some(b)) => cmp(a, b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return None,
+        };
+        match ord {
+            Ordering::Less => self.next().map(MergeItem::Left),
+            Ordering::Greater => other.next().map(MergeItem::Right),
+            Ordering::Equal => Some(MergeItem::Both(self.next()?, other.next()?)),
+        }
+    }
+
+    /// Convert this iterator into an iterator that yields a clone of `sep`
+    /// between each pair of consecutive elements.
+    ///
+    /// A forward peek decides whether another element follows.
+    /// Therefore, no trailing separator is yielded and `FWD_SIZE` must be at least 1.
+    ///
+    /// Separators are not recorded in the backward peek buffer.
+    pub fn intersperse(mut self, sep: I::Item) -> impl Iterator<Item = I::Item> {
+        let mut need_sep = false;
+        core::iter::from_fn(move || {
+            if need_sep {
+                need_sep = false;
+                return Some(sep.clone());
+            }
+            let item = self.next()?;
+            need_sep = self.peek_fwd().is_some();
+            Some(item)
+        })
+    }
+
+    /// Limit the number of elements that a single call of a consuming search may consume.
+    ///
+    /// This guards searches like [Self::position_fwd], [Self::consume_until]
+    /// and [Self::consume_run] against endless loops on infinite iterators,
+    /// e.g. [Iterator::cycle].
+    /// `None` removes the limit. By default there is no limit.
+    #[inline]
+    pub fn set_consume_limit(&mut self, n: Option<usize>) {
+        self.consume_limit = n;
+    }
+
+    /// Check whether `consumed` elements reach the limit set with [Self::set_consume_limit].
+    fn consume_limit_reached(&self, consumed: usize) -> bool {
+        self.consume_limit.is_some_and(|limit| consumed >= limit)
+    }
+
+    /// Consume elements up to and including the first element for which `pred` returns true.
+    ///
+    /// This is like [Iterator::position],
+    /// but the consumed elements are recorded in the backward peek buffer.
+    ///
+    /// Returns the index of the matching element relative to the current position, or:
+    /// - [PeekError::Exhausted], if the inner iterator is exhausted without a match, or
+    /// - [PeekError::LimitReached], if the limit set with [Self::set_consume_limit]
+    ///   has been consumed without a match.
+    pub fn position_fwd<F>(&mut self, mut pred: F) -> Result<usize, PeekError>
+    where
+        F: FnMut(&I::Item) -> bool,
+    {
+        let mut count = 0;
+        loop {
+            if let Some(limit) = self.consume_limit.filter(|&limit| count >= limit) {
+                return Err(PeekError::LimitReached { limit });
+            }
+            let item = self.next().ok_or(PeekError::Exhausted)?;
+            if pred(&item) {
+                return Ok(count);
+            }
+            count += 1;
+        }
+    }
+
+    /// Consume elements until the next element is one for which `pred` returns true.
+    ///
+    /// The matching element is not consumed.
+    /// The consumed elements are recorded in the backward peek buffer.
+    /// The decision is made with a forward peek.
+    /// Therefore, `FWD_SIZE` must be at least 1.
+    ///
+    /// Returns the number of consumed elements, or:
+    /// - [PeekError::Exhausted], if the inner iterator is exhausted without a match, or
+    /// - [PeekError::LimitReached], if the limit set with [Self::set_consume_limit]
+    ///   has been consumed without a match.
+    pub fn consume_until<F>(&mut self, mut pred: F) -> Result<usize, PeekError>
+    where
+        F: FnMut(&I::Item) -> bool,
+    {
+        let mut count = 0;
+        loop {
+            match self.peek_fwd() {
+                None => return Err(PeekError::Exhausted),
+                Some(item) if pred(item) => return Ok(count),
+                Some(_) => (),
+            }
+            if let Some(limit) = self.consume_limit.filter(|&limit| count >= limit) {
+                return Err(PeekError::LimitReached { limit });
+            }
+            self.next();
+            count += 1;
+        }
+    }
+
+    /// Get the buffered elements of the forward peek buffer as two slices.
+    ///
+    /// The forward peek buffer is a ring buffer.
+    /// The first slice holds the next elements and the second slice holds the elements after them.
+    /// Concatenating the first and the second slice gives the elements in stream order,
+    /// i.e. in the order of [Self::peek_fwd_nth]. The second slice may be empty.
+    ///
+    /// This does not pull any elements from the inner iterator.
+    #[inline]
+    pub fn fwd_as_slices(&self) -> (&[I::Item], &[I::Item]) {
+        self.fwd_buf.as_slices()
+    }
+
+    /// Get an iterator over the elements of the backward peek buffer.
+    ///
+    /// The iterator yields the most recently consumed element first,
+    /// i.e. in the order of [Self::peek_bwd_nth].
+    #[inline]
+    pub fn peek_bwd_iter(&self) -> impl Iterator<Item = &I::Item> {
+        self.bwd_buf.iter().take(self.bwd_buffered())
+    }
+
+    /// Get the elements of the backward peek buffer as two slices.
+    ///
+    /// The backward peek buffer is a ring buffer.
+    /// The first slice holds the most recently consumed elements
+    /// and the second slice holds the elements consumed before them.
+    /// Concatenating the first and the second slice gives the elements
+    /// from most recent to oldest, i.e. in the order of [Self::peek_bwd_nth]
+    /// and [Self::peek_bwd_iter]. The second slice may be empty.
+    pub fn bwd_as_slices(&self) -> (&[I::Item], &[I::Item]) {
+        let len = self.bwd_buffered();
+        let (front, back) = self.bwd_buf.as_slices();
+        if front.len() >= len {
+            (&front[..len], &[])
+        } else {
+            (front, &back[..len - front.len()])
+        }
+    }
+
+    /// Return an iterator that consumes elements from this iterator and yields `f` applied to them,
+    /// as long as `f` returns Some.
+    ///
+    /// Unlike [Iterator::map_while], the first element for which `f` returns None
+    /// is not consumed. It remains available to [Self::peek_fwd] and [Self::next].
+    ///
+    /// The decision is made with a forward peek.
+    /// Therefore, `FWD_SIZE` must be at least 1.
+    pub fn map_while_peek<'a, B, F>(&'a mut self, mut f: F) -> impl Iterator<Item = B> + 'a
+    where
+        F: FnMut(&I::Item) -> Option<B> + 'a,
+    {
+        let mut done = false;
+        core::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            match f(self.peek_fwd()?) {
+                Some(b) => {
+                    self.next();
+                    Some(b)
+                }
+                None => {
+                    done = true;
+                    None
+                }
+            }
+        })
+    }
+
+    /// Get a closure that returns a clone of the n-th upcoming element,
+    /// like [Self::peek_fwd_nth].
+    ///
+    /// The closure fills the forward peek buffer as needed and borrows this iterator mutably.
+    /// It does not consume elements.
+    pub fn peeker(&mut self) -> impl FnMut(usize) -> Option<I::Item> + '_ {
+        move |i| self.peek_fwd_nth(i).cloned()
+    }
+
+    /// Get the index of the upcoming element with the smallest `key`.
+    ///
+    /// The forward peek buffer is filled up to `FWD_SIZE` elements.
+    /// If several elements have the smallest key, then the earliest index is returned.
+    /// This does not consume elements.
+    ///
+    /// Returns None, if there is no upcoming element.
+    pub fn fwd_min_by_key<B, F>(&mut self, key: F) -> Option<usize>
+    where
+        B: Ord,
+        F: FnMut(&I::Item) -> B,
+    {
+        self.fwd_best_by_key(key, Ordering::Less)
+    }
+
+    /// Get the index of the upcoming element with the largest `key`.
+    ///
+    /// The forward peek buffer is filled up to `FWD_SIZE` elements.
+    /// If several elements have the largest key, then the earliest index is returned.
+    /// This does not consume elements.
+    ///
+    /// Returns None, if there is no upcoming element.
+    pub fn fwd_max_by_key<B, F>(&mut self, key: F) -> Option<usize>
+    where
+        B: Ord,
+        F: FnMut(&I::Item) -> B,
+    {
+        self.fwd_best_by_key(key, Ordering::Greater)
+    }
+
+    /// Get the index of the earliest upcoming element with the best `key`.
+    ///
+    /// A key is better than another key, if comparing them yields `better`.
+    fn fwd_best_by_key<B, F>(&mut self, mut key: F, better: Ordering) -> Option<usize>
+    where
+        B: Ord,
+        F: FnMut(&I::Item) -> B,
+    {
+        self.fill_fwd();
+        let mut best: Option<(usize, B)> = None;
+        for (i, item) in self.fwd_buf.iter().enumerate() {
+            let k = key(item);
+            if best.as_ref().map_or(true, |(_, b)| k.cmp(b) == better) {
+                best = Some((i, k));
+            }
+        }
+        best.map(|(i, _)| i)
+    }
+
+    /// Get the number of elements that [Self::consume_until] would consume,
+    /// without consuming anything.
+    ///
+    /// The forward peek buffer is filled as needed.
+    ///
+    /// Returns None, if no upcoming element within the next `FWD_SIZE` elements
+    /// satisfies `is_end`.
+    pub fn dry_run_consume_until<F>(&mut self, mut is_end: F) -> Option<usize>
+    where
+        F: FnMut(&I::Item) -> bool,
+    {
+        (0..FWD_SIZE).find(|&i| self.peek_fwd_nth(i).is_some_and(&mut is_end))
+    }
+
+    /// Get an iterator that yields clones of the elements of the backward peek buffer
+    /// in chronological order, i.e. from oldest to newest.
+    ///
+    /// This is the reverse order of [Self::peek_bwd_iter].
+    pub fn replay_bwd(&self) -> impl Iterator<Item = I::Item> + '_ {
+        self.bwd_buf.iter().take(self.bwd_buffered()).rev().cloned()
+    }
+
+    /// Write the indices of all upcoming elements for which `pred` returns true into `out`.
+    ///
+    /// The forward peek buffer is filled up to `FWD_SIZE` elements.
+    /// At most `out.len()` indices are written, in ascending order.
+    /// This does not consume elements.
+    ///
+    /// Returns the number of indices written to `out`.
+    pub fn fwd_find_all<F>(&mut self, mut pred: F, out: &mut [usize]) -> usize
+    where
+        F: FnMut(&I::Item) -> bool,
+    {
+        self.fill_fwd();
+        let matches = self
+            .fwd_buf
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| pred(item))
+            .map(|(i, _)| i);
+        let mut count = 0;
+        for (slot, i) in out.iter_mut().zip(matches) {
+            *slot = i;
+            count += 1;
+        }
+        count
+    }
+
+    /// Get the age of the oldest element in the backward peek buffer.
+    ///
+    /// The age is simply the number of elements in the history, [Self::bwd_buffered].
+    /// This includes elements seeded with [Self::with_bwd_history],
+    /// so the age can be larger than [Self::stream_pos].
+    ///
+    /// Returns None, if the backward peek buffer is empty.
+    #[inline]
+    pub fn bwd_oldest_age(&self) -> Option<usize> {
+        Some(self.bwd_buffered()).filter(|&age| age > 0)
+    }
+
+    /// Get the index of the first upcoming element for which `pred` returns false,
+    /// like [slice::partition_point].
+    ///
+    /// The forward peek buffer is filled up to `FWD_SIZE` elements.
+    /// The window must be partitioned,
+    /// i.e. `pred` returns true for all elements before the partition point
+    /// and false for all elements starting at the partition point.
+    /// Otherwise the result is unspecified.
+    /// The partition point is found with a binary search.
+    /// This does not consume elements.
+    ///
+    /// Returns the number of buffered elements, if `pred` returns true for all of them.
+    pub fn fwd_partition_point<F>(&mut self, mut pred: F) -> usize
+    where
+        F: FnMut(&I::Item) -> bool,
+    {
+        let (mut lo, mut hi) = (0, self.fill_fwd());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if pred(&self.fwd_buf[mid]) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Set a record sentinel.
+    ///
+    /// Consuming an element that is equal to `s` clears the backward peek buffer.
+    /// The sentinel itself is not recorded.
+    /// Therefore, backward peeks never reach across a record boundary,
+    /// e.g. when `s` is the line separator of line oriented input.
+    ///
+    /// If the backward peek buffer already holds a sentinel,
+    /// then the sentinel and all elements before it are dropped from the backward peek buffer.
+    ///
+    /// The clearing happens silently inside of [Self::next] and all other consuming methods.
+    /// The cleared elements are not reported as evicted by [Self::next_checked_bwd]
+    /// and a following [Self::step_back] can not rewind across the sentinel.
+    ///
+    /// See [Self::clear_record_sentinel].
+    pub fn set_record_sentinel(&mut self, s: I::Item)
+    where
+        I::Item: PartialEq,
+    {
+        let found = self.peek_bwd_iter().position(|item| *item == s);
+        if let Some(k) = found {
+            for _ in k..self.bwd_buffered() {
+                self.bwd_buf.remove(k);
+            }
+        }
+        self.sentinel = Some((s, <I::Item as PartialEq>::eq));
+    }
+
+    /// Remove the record sentinel set with [Self::set_record_sentinel].
+    #[inline]
+    pub fn clear_record_sentinel(&mut self) {
+        self.sentinel = None;
+    }
+
+    /// Consume the upcoming elements as long as they match `pattern` element by element.
+    ///
+    /// Unlike [Self::consume_longest_fwd_prefix], this is not all-or-nothing:
+    /// On a mismatch, the matching prefix of `pattern` stays consumed.
+    /// The mismatching element is not consumed.
+    /// The consumed elements are recorded in the backward peek buffer.
+    /// `pattern` is not limited by `FWD_SIZE`.
+    ///
+    /// Returns `Ok(pattern.len())`, if the whole pattern matched,
+    /// or `Err(k)`, if the element at pattern index `k` did not match
+    /// or the stream ended there. In that case `k` elements have been consumed.
+    pub fn expect_slice<T>(&mut self, pattern: &[T]) -> Result<usize, usize>
+    where
+        I::Item: PartialEq<T>,
+    {
+        for (k, p) in pattern.iter().enumerate() {
+            match self.peek_fwd() {
+                Some(item) if item == p => {
+                    self.next();
+                }
+                _ => return Err(k),
+            }
+        }
+        Ok(pattern.len())
+    }
+
+    /// Peek the next element, like [Self::peek_fwd],
+    /// and call `f` with the peeked element.
+    ///
+    /// The borrow of the peeked element ends when `f` returns.
+    /// Returns the return value of `f`.
+    #[inline]
+    pub fn peek_fwd_scope<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(Option<&I::Item>) -> R,
+    {
+        f(self.peek_fwd())
+    }
+
+    /// Peek the next n-th element, like [Self::peek_fwd_nth],
+    /// and call `f` with the peeked element.
+    ///
+    /// The borrow of the peeked element ends when `f` returns.
+    /// Returns the return value of `f`.
+    #[inline]
+    pub fn peek_fwd_nth_scope<R, F>(&mut self, i: usize, f: F) -> R
+    where
+        F: FnOnce(Option<&I::Item>) -> R,
+    {
+        f(self.peek_fwd_nth(i))
+    }
+
+    /// Consume elements up to and including the first element for which `pred` returns true,
+    /// like [Self::position_fwd], but consume at most `max_consume` elements.
+    ///
+    /// The consumed elements are recorded in the backward peek buffer.
+    ///
+    /// Returns the index of the matching element relative to the current position,
+    /// or `Err(consumed)` with the number of consumed elements,
+    /// if the budget or the inner iterator is exhausted without a match.
+    pub fn position_fwd_within<F>(
+        &mut self,
+        max_consume: usize,
+        mut pred: F,
+    ) -> Result<usize, usize>
+    where
+        F: FnMut(&I::Item) -> bool,
+    {
+        for k in 0..max_consume {
+            match self.next() {
+                Some(item) if pred(&item) => return Ok(k),
+                Some(_) => (),
+                None => return Err(k),
+            }
+        }
+        Err(max_consume)
+    }
+
+    /// Check whether `FWD_SIZE` is large enough to see the next element
+    /// for which `pred` returns true.
+    ///
+    /// The forward peek buffer is filled as needed, up to `FWD_SIZE` elements.
+    /// This does not consume elements.
+    ///
+    /// Returns the index of the matching element within the forward peek window.
+    /// A `FWD_SIZE` of at least the index plus one is sufficient to see the element.
+    /// Returns None, if there is no match within the window.
+    /// That signals that `FWD_SIZE` might be too small,
+    /// unless the inner iterator is exhausted within the window.
+    #[inline]
+    pub fn suggest_fwd_size<F>(&mut self, pred: F) -> Option<usize>
+    where
+        F: FnMut(&I::Item) -> bool,
+    {
+        self.dry_run_consume_until(pred)
+    }
+
+    /// Count the occurrences of each element in the forward peek window.
+    ///
+    /// The forward peek buffer is filled up to `FWD_SIZE` elements.
+    /// This does not consume elements.
+    ///
+    /// This is only available with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn fwd_counts(&mut self) -> BTreeMap<I::Item, usize>
+    where
+        I::Item: Ord,
+    {
+        self.fill_fwd();
+        let mut counts = BTreeMap::new();
+        for item in &self.fwd_buf {
+            *counts.entry(item.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Convert this iterator into an iterator over [Vec] segments
+    /// between the elements for which `is_sep` returns true.
+    ///
+    /// This is the streaming analog of [str::split]:
+    /// The separators are consumed, but not included in any segment.
+    /// Leading, trailing and consecutive separators produce empty segments.
+    /// An empty stream produces one empty segment.
+    /// The consumed elements, including the separators,
+    /// are recorded in the backward peek buffer.
+    ///
+    /// This is only available with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn split<F>(mut self, mut is_sep: F) -> impl Iterator<Item = Vec<I::Item>>
+    where
+        F: FnMut(&I::Item) -> bool,
+    {
+        let mut done = false;
+        core::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let mut segment = Vec::new();
+            loop {
+                match self.next() {
+                    Some(item) if is_sep(&item) => return Some(segment),
+                    Some(item) => segment.push(item),
+                    None => {
+                        done = true;
+                        return Some(segment);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Check whether this iterator has advanced beyond `prev_position`,
+    /// which has earlier been captured with [Self::stream_pos].
+    ///
+    /// Peeking does not advance the iterator.
+    /// This can be used to detect loops that don't make progress.
+    #[inline]
+    pub fn assert_progress(&self, prev_position: usize) -> bool {
+        self.pos > prev_position
+    }
+
+    /// Get mutable access to the buffered elements of the forward peek buffer as two slices.
+    ///
+    /// The slices are ordered like in [Self::fwd_as_slices]:
+    /// Concatenating the first and the second slice gives the elements in stream order.
+    /// The second slice may be empty.
+    /// Modified elements are returned by the following calls to [Self::next].
+    /// Elements held in slots borrowed by [Self::peek_fwd_nth_deep] are not included.
+    ///
+    /// This does not pull any elements from the inner iterator.
+    #[inline]
+    pub fn fwd_slices_mut(&mut self) -> (&mut [I::Item], &mut [I::Item]) {
+        self.fwd_buf.as_mut_slices()
+    }
+}
+
+impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Iterator for Peekable<I, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_checked_bwd().0
+    }
+}
+
+/// Cursor for navigating within the buffered elements of a [Peekable].
+///
+/// The cursor offset is within `-bwd_buffered() .. fwd_buffered()`:
+/// - Offset 0 and positive offsets refer to the forward peek buffer.
+///   Offset 0 is the next element.
+/// - Negative offsets refer to the backward peek buffer.
+///   Offset -1 is the element that has last been returned from [Peekable::next].
+///
+/// See [Peekable::cursor].
+pub struct Cursor<'a, I, const BWD_SIZE: usize, const FWD_SIZE: usize>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    peekable: &'a Peekable<I, BWD_SIZE, FWD_SIZE>,
+    offset: isize,
+}
+
+impl<'a, I, const BWD_SIZE: usize, const FWD_SIZE: usize> Cursor<'a, I, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    /// Get the current cursor offset.
+    #[inline]
+    pub fn offset(&self) -> isize {
+        self.offset
+    }
+
+    /// Get the element at the current cursor offset.
+    ///
+    /// Returns None, if there is no buffered element at the current offset.
+    pub fn current(&self) -> Option<&'a I::Item> {
+        if self.offset >= 0 {
+            self.peekable.fwd_buf.get(self.offset as usize)
+        } else {
+            self.peekable.bwd_buf.get((-self.offset - 1) as usize)
+        }
+    }
+
+    /// Move the cursor one element towards the future.
+    ///
+    /// Returns false and does not move, if the cursor is at the end of the forward buffer.
+    pub fn move_next(&mut self) -> bool {
+        if self.offset + 1 < self.peekable.fwd_buffered() as isize {
+            self.offset += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Move the cursor one element towards the past.
+    ///
+    /// Returns false and does not move, if the cursor is at the end of the backward buffer.
+    pub fn move_prev(&mut self) -> bool {
+        if self.offset > -(self.peekable.bwd_buffered() as isize) {
+            self.offset -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Peekable<I, BWD_SIZE, FWD_SIZE>
+where
+    I: DoubleEndedIterator,
+    I::Item: Clone,
+{
+    /// Remove up to `n` elements from the back of the inner iterator.
+    ///
+    /// Only the part of the inner iterator that has not been pulled into
+    /// the forward peek buffer, yet, is affected.
+    /// Elements that are already held in the forward peek buffer are not touched
+    /// and will still be returned by [Self::next].
+    ///
+    /// The removed elements are dropped.
+    ///
+    /// Returns the number of elements that have actually been removed.
+    pub fn next_back_n(&mut self, n: usize) -> usize {
+        let mut count = 0;
+        while count < n && self.iter.next_back().is_some() {
+            count += 1;
+        }
+        count
+    }
+}
+
+/// [Peekable] that keeps a running hash of all consumed elements.
+///
+/// Peeking does not change the hash.
+/// Only elements that are returned from [Iterator::next] are fed into the [Hasher].
+///
+/// See [Peekable::with_hasher].
+pub struct Hashing<I, H, const BWD_SIZE: usize, const FWD_SIZE: usize>
+where
+    I: Iterator,
+    I::Item: Clone + Hash,
+    H: Hasher,
+{
+    peekable: Peekable<I, BWD_SIZE, FWD_SIZE>,
+    hasher: H,
+}
+
+impl<I, H, const BWD_SIZE: usize, const FWD_SIZE: usize> Hashing<I, H, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone + Hash,
+    H: Hasher,
+{
+    /// Get the current hash value of all consumed elements.
+    #[inline]
+    pub fn consumed_hash(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    delegate_peek!(peekable);
+
+    /// Unwrap into the inner [Peekable] and the [Hasher].
+    #[inline]
+    pub fn into_inner(self) -> (Peekable<I, BWD_SIZE, FWD_SIZE>, H) {
+        (self.peekable, self.hasher)
+    }
+}
+
+impl<I, H, const BWD_SIZE: usize, const FWD_SIZE: usize> Iterator
+    for Hashing<I, H, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone + Hash,
+    H: Hasher,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.peekable.next();
+        if let Some(item) = &item {
+            item.hash(&mut self.hasher);
+        }
+        item
+    }
+}
+
+impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Peekable<I, BWD_SIZE, FWD_SIZE>
+where
+    I: ExactSizeIterator,
+    I::Item: Clone,
+{
+    /// Get the number of remaining elements.
+    ///
+    /// This includes the elements in the forward peek buffer
+    /// and the elements that have not been pulled from the inner iterator, yet.
+    ///
+    /// This is only available, if the inner iterator is an [ExactSizeIterator].
+    #[inline]
+    pub fn remaining(&self) -> Option<usize> {
+        Some(self.iter.len() + self.fwd_buffered() + self.deep)
+    }
+}
+
+/// Cheap snapshot token of the state of a [Peekable].
+///
+/// The token holds the stream position and the backward buffer occupancy.
+/// Comparisons consider both, ordered by the stream position first.
+/// Peeking forward changes neither of them,
+/// so tokens captured before and after a peek compare equal.
+///
+/// See [Peekable::peek_cursor].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PeekCursor {
+    position: usize,
+    bwd_buffered: usize,
+}
+
+impl PeekCursor {
+    /// Get the captured stream position. See [Peekable::stream_pos].
+    #[inline]
+    pub fn stream_pos(&self) -> usize {
+        self.position
+    }
+
+    /// Get the captured backward buffer occupancy. See [Peekable::bwd_buffered].
+    #[inline]
+    pub fn bwd_buffered(&self) -> usize {
+        self.bwd_buffered
+    }
+}
+
+/// Transaction guard on a [Peekable].
+///
+/// Elements can be consumed through the guard.
+/// When the guard is dropped without a call to [Self::commit],
+/// all elements consumed since the start of the transaction are un-consumed,
+/// as if [Peekable::step_back_by] had been called.
+///
+/// A rollback is limited by the backward and forward peek buffer capacities.
+/// See [Peekable::max_rewind].
+/// Dropping a guard that can not be fully rolled back rolls back as far as possible
+/// and silently ignores the rest.
+/// Use [Self::rollback] to detect this case explicitly.
+///
+/// See [Peekable::transaction].
+pub struct Transaction<'a, I, const BWD_SIZE: usize, const FWD_SIZE: usize>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    peekable: &'a mut Peekable<I, BWD_SIZE, FWD_SIZE>,
+    start: usize,
+    done: bool,
+}
+
+impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Transaction<'_, I, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    /// Keep all elements consumed in this transaction.
+    #[inline]
+    pub fn commit(mut self) {
+        self.done = true;
+    }
+
+    /// Un-consume all elements consumed in this transaction.
+    ///
+    /// Returns [PeekError::OverCapacity], if not all elements could be un-consumed.
+    /// `requested` is the number of elements consumed in this transaction
+    /// and `capacity` is the number of elements that could be un-consumed.
+    #[inline]
+    pub fn rollback(mut self) -> Result<(), PeekError> {
+        self.do_rollback()
+    }
+
+    /// Get the number of elements that have been consumed since the start of this transaction.
+    ///
+    /// Peeking does not change this number.
+    #[inline]
+    pub fn transaction_advanced(&self) -> usize {
+        self.peekable.pos.saturating_sub(self.start)
+    }
+
+    fn do_rollback(&mut self) -> Result<(), PeekError> {
+        self.done = true;
+        let advanced = self.transaction_advanced();
+        let rewound = self.peekable.step_back_by(advanced);
+        if rewound == advanced {
+            Ok(())
+        } else {
+            Err(PeekError::OverCapacity {
+                requested: advanced,
+                capacity: rewound,
+            })
+        }
+    }
+
+    delegate_peek!(peekable);
+}
+
+impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Iterator
+    for Transaction<'_, I, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.peekable.next()
+    }
+}
+
+impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Drop
+    for Transaction<'_, I, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    fn drop(&mut self) {
+        if !self.done {
+            // Don't panic in drop. This might be running during unwinding.
+            let _ = self.do_rollback();
+        }
+    }
+}
+
+/// Restricted mutable view on a [Peekable] for sub-routines.
+///
+/// Elements consumed through the sub-scanner are consumed from the parent [Peekable].
+/// Operations that reconfigure or convert the parent are not available.
+///
+/// See [Peekable::sub].
+pub struct SubScanner<'a, I, const BWD_SIZE: usize, const FWD_SIZE: usize>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    peekable: &'a mut Peekable<I, BWD_SIZE, FWD_SIZE>,
+}
+
+impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> SubScanner<'_, I, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    /// Get a nested [SubScanner] for handing this sub-scanner to a further sub-routine.
+    #[inline]
+    pub fn sub(&mut self) -> SubScanner<'_, I, BWD_SIZE, FWD_SIZE> {
+        self.peekable.sub()
+    }
+
+    /// See [Peekable::stream_pos].
+    #[inline]
+    pub fn stream_pos(&self) -> usize {
+        self.peekable.pos
+    }
+
+    delegate_peek!(peekable);
+}
+
+impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Iterator
+    for SubScanner<'_, I, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.peekable.next()
+    }
+}
+
+/// [Peekable] that calls a closure for every consumed element.
+///
+/// The closure is called in [Iterator::next] after the element has been taken
+/// and before it enters the backward peek buffer.
+/// Peeking does not call the closure.
+///
+/// See [Peekable::on_consume].
+pub struct Observed<I, F, const BWD_SIZE: usize, const FWD_SIZE: usize>
+where
+    I: Iterator,
+    I::Item: Clone,
+    F: FnMut(&I::Item),
+{
+    peekable: Peekable<I, BWD_SIZE, FWD_SIZE>,
+    f: F,
+}
+
+impl<I, F, const BWD_SIZE: usize, const FWD_SIZE: usize> Observed<I, F, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone,
+    F: FnMut(&I::Item),
+{
+    /// See [Peekable::stream_pos].
+    #[inline]
+    pub fn stream_pos(&self) -> usize {
+        self.peekable.pos
+    }
+
+    delegate_peek!(peekable);
+
+    /// Unwrap into the inner [Peekable] and the closure.
+    #[inline]
+    pub fn into_inner(self) -> (Peekable<I, BWD_SIZE, FWD_SIZE>, F) {
+        (self.peekable, self.f)
+    }
+}
+
+impl<I, F, const BWD_SIZE: usize, const FWD_SIZE: usize> Iterator
+    for Observed<I, F, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone,
+    F: FnMut(&I::Item),
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.peekable.pull()?;
+        (self.f)(&item);
+        self.peekable.record(&item);
+        Some(item)
+    }
+}
+
+impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Peekable<I, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator<Item = char>,
+{
+    /// Check whether the upcoming [char]s are equal to the string `s`.
+    ///
+    /// Returns false, if `s` has more [char]s than `FWD_SIZE`.
+    /// This does not consume elements.
+    pub fn peek_fwd_matches_str(&mut self, s: &str) -> bool {
+        s.chars().count() <= FWD_SIZE
+            && s.chars()
+                .enumerate()
+                .all(|(i, c)| self.peek_fwd_nth(i) == Some(&c))
+    }
+
+    /// Consume a leading byte order mark `'\u{FEFF}'`, if the next [char] is one.
+    ///
+    /// The byte order mark is recorded in the backward peek buffer like any other element.
+    ///
+    /// Returns true, if a byte order mark has been consumed.
+    pub fn skip_bom(&mut self) -> bool {
+        if self.peek_fwd() == Some(&'\u{FEFF}') {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Check whether the upcoming [char]s match the shell-style glob `pattern`.
+    ///
+    /// - `?` matches any single [char].
+    /// - A trailing `*` matches any remaining [char]s, including none.
+    ///   A `*` at any other place matches a literal `*`.
+    /// - Any other [char] matches itself.
+    ///
+    /// Without a trailing `*`, the stream must end right after the matched [char]s.
+    /// Only the forward peek window is visible:
+    /// Returns false, if the pattern (without the trailing `*`) has more [char]s than `FWD_SIZE`,
+    /// or if it has exactly `FWD_SIZE` [char]s and no trailing `*`,
+    /// because the end of the stream can't be seen.
+    /// This does not consume elements.
+    pub fn fwd_glob_match(&mut self, pattern: &str) -> bool {
+        let (pattern, any_suffix) = match pattern.strip_suffix('*') {
+            Some(prefix) => (prefix, true),
+            None => (pattern, false),
+        };
+        let len = pattern.chars().count();
+        if len > FWD_SIZE || (!any_suffix && len == FWD_SIZE) {
+            return false;
+        }
+        let prefix_matches = pattern
+            .chars()
+            .enumerate()
+            .all(|(i, p)| match self.peek_fwd_nth(i) {
+                Some(c) => p == '?' || p == *c,
+                None => false,
+            });
+        prefix_matches && (any_suffix || self.peek_fwd_nth(len).is_none())
+    }
+}
+
+/// Iterator over the remaining elements of a [Peekable].
+///
+/// The elements held in the forward peek buffer are returned first,
+/// followed by the elements of the inner iterator.
+/// Returned elements are not recorded in the backward peek buffer.
+///
+/// See [Peekable::flatten_peekable] and [Peekable::flat_map_peek].
+pub struct Remaining<I, const BWD_SIZE: usize, const FWD_SIZE: usize>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    peekable: Peekable<I, BWD_SIZE, FWD_SIZE>,
+}
+
+impl<I, const BWD_SIZE: usize, const FWD_SIZE: usize> Iterator for Remaining<I, BWD_SIZE, FWD_SIZE>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.peekable.pull()
+    }
+}
+
+/// Element wrapper that implements [Clone] with a custom clone function.
+///
+/// See [Peekable::new_cloneable].
+#[derive(Debug)]
+pub struct Cloneable<T> {
+    value: T,
+    clone: fn(&T) -> T,
+}
+
+impl<T> Cloneable<T> {
+    /// Wrap `value`, so that it is cloned with `clone`.
+    #[inline]
+    pub fn new(value: T, clone: fn(&T) -> T) -> Self {
+        Self { value, clone }
+    }
+
+    /// Get a reference to the wrapped value.
+    #[inline]
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Unwrap the value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Clone for Cloneable<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: (self.clone)(&self.value),
+            clone: self.clone,
+        }
+    }
+}
+
+impl<T> core::ops::Deref for Cloneable<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Iterator that wraps every element of `I` into a [Cloneable].
+///
+/// See [Peekable::new_cloneable].
+#[derive(Debug)]
+pub struct CloneableIter<I>
+where
+    I: Iterator,
+{
+    iter: I,
+    clone: fn(&I::Item) -> I::Item,
+}
+
+impl<I> Iterator for CloneableIter<I>
+where
+    I: Iterator,
+{
+    type Item = Cloneable<I::Item>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let clone = self.clone;
+        self.iter.next().map(|value| Cloneable::new(value, clone))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{iter::Copied, slice::Iter};
+
+    #[cfg(feature = "alloc")]
+    use alloc::vec;
+
+    /// Iterator wrapper that counts the calls to [Iterator::next].
+    struct Counting<'a, I> {
+        iter: I,
+        count: &'a core::cell::Cell<usize>,
+    }
+
+    impl<I: Iterator> Iterator for Counting<'_, I> {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.count.set(self.count.get() + 1);
+            self.iter.next()
+        }
+    }
+
+    #[test]
+    fn test_next() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Iter<i32>, 4, 4>::new(&a);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_peek_fwd() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Iter<i32>, 4, 4>::new(&a);
+
+        assert_eq!(it.peek(), Some(&&1));
+        assert_eq!(it.peek(), Some(&&1));
+        assert_eq!(it.peek_fwd(), Some(&&1));
+        assert_eq!(it.peek_fwd_nth(0), Some(&&1));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+        assert_eq!(it.peek_fwd_nth(2), Some(&&3));
+        assert_eq!(it.peek_fwd_nth(3), None);
+        assert_eq!(it.peek_fwd_nth(4), None);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek(), Some(&&2));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&3));
+    }
+
+    #[test]
+    fn test_peek_fwd_lim() {
+        let a = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(it.peek_fwd_nth(0), Some(&&1));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+        assert_eq!(it.peek_fwd_nth(2), Some(&&3));
+        assert_eq!(it.peek_fwd_nth(3), Some(&&4));
+        assert_eq!(it.peek_fwd_nth(4), None);
+        assert_eq!(it.peek_fwd_nth(5), None);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_fwd_nth(0), Some(&&2));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&3));
+        assert_eq!(it.peek_fwd_nth(2), Some(&&4));
+        assert_eq!(it.peek_fwd_nth(3), Some(&&5));
+        assert_eq!(it.peek_fwd_nth(4), None);
+        assert_eq!(it.peek_fwd_nth(5), None);
+    }
+
+    #[test]
+    fn test_peek_bwd() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Iter<i32>, 4, 4>::new(&a);
+
+        assert_eq!(it.peek_bwd(), None);
+        assert_eq!(it.peek_bwd_nth(0), None);
+        assert_eq!(it.peek_bwd_nth(1), None);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_bwd(), Some(&&1));
+        assert_eq!(it.peek_bwd_nth(1), None);
+        assert_eq!(it.peek_bwd_nth(2), None);
+
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.peek_bwd(), Some(&&2));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&1));
+        assert_eq!(it.peek_bwd_nth(2), None);
+    }
+
+    #[test]
+    fn test_peek_bwd_lim() {
+        let a = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.peek_bwd_nth(0), Some(&&3));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&2));
+        assert_eq!(it.peek_bwd_nth(2), None);
         assert_eq!(it.peek_bwd_nth(3), None);
     }
+
+    #[test]
+    fn test_rewind_one_if() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Iter<i32>, 4, 4>::new(&a);
+
+        // Empty backward buffer.
+        assert!(!it.rewind_one_if(|_| true));
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+
+        // Predicate false.
+        assert!(!it.rewind_one_if(|&&x| x == 1));
+        assert_eq!(it.peek_bwd(), Some(&&2));
+
+        // Predicate true.
+        assert!(it.rewind_one_if(|&&x| x == 2));
+        assert_eq!(it.peek_bwd(), Some(&&1));
+        assert_eq!(it.peek(), Some(&&2));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_rewind_one_if_fwd_full() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 4, 2>::new(&a);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&3));
+
+        // Forward buffer full.
+        assert!(!it.rewind_one_if(|_| true));
+        assert_eq!(it.peek_bwd(), Some(&&1));
+        assert_eq!(it.next(), Some(&2));
+    }
+
+    #[test]
+    fn test_next_checked_bwd() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(it.next_checked_bwd(), (Some(&1), None));
+        assert_eq!(it.next_checked_bwd(), (Some(&2), None));
+        assert_eq!(it.next_checked_bwd(), (Some(&3), Some(&1)));
+        assert_eq!(it.next_checked_bwd(), (Some(&4), Some(&2)));
+        assert_eq!(it.next_checked_bwd(), (None, None));
+        assert_eq!(it.peek_bwd_nth(0), Some(&&4));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&3));
+    }
+
+    #[test]
+    fn test_with_position() {
+        let a: [i32; 0] = [];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a).with_position();
+        assert_eq!(it.next(), None);
+
+        let a = [1];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a).with_position();
+        assert_eq!(it.next(), Some((Position::Only, &1)));
+        assert_eq!(it.next(), None);
+
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a).with_position();
+        assert_eq!(it.next(), Some((Position::First, &1)));
+        assert_eq!(it.next(), Some((Position::Middle, &2)));
+        assert_eq!(it.next(), Some((Position::Middle, &3)));
+        assert_eq!(it.next(), Some((Position::Last, &4)));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_dedup() {
+        let a = [1, 1, 2, 3, 3, 3, 2];
+        let mut expected = [0; 7];
+        let mut len = 0;
+        for x in a {
+            if len == 0 || expected[len - 1] != x {
+                expected[len] = x;
+                len += 1;
+            }
+        }
+
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a).dedup();
+        for x in &expected[..len] {
+            assert_eq!(it.next(), Some(x));
+        }
+        assert_eq!(it.next(), None);
+        assert_eq!(len, 4);
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let a = [1, 2, 3, 4, 5, 6, 1, 1];
+        let mut it =
+            Peekable::<Copied<Iter<i32>>, 2, 2>::new(a.iter().copied()).coalesce(|x, y| {
+                if x + y <= 10 {
+                    Ok(x + y)
+                } else {
+                    Err((x, y))
+                }
+            });
+        assert_eq!(it.next(), Some(10));
+        assert_eq!(it.next(), Some(5));
+        assert_eq!(it.next(), Some(8));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_peek_fwd_nth_max() {
+        let a = [1, 2, 3];
+        let count = core::cell::Cell::new(0);
+        let mut it = Peekable::<Counting<Iter<i32>>, 2, 2>::new(Counting {
+            iter: a.iter(),
+            count: &count,
+        });
+
+        assert_eq!(it.peek_fwd_nth(usize::MAX), None);
+        assert_eq!(count.get(), 0);
+        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+        assert_eq!(count.get(), 2);
+        assert_eq!(it.peek_fwd_nth(usize::MAX), None);
+        assert_eq!(count.get(), 2);
+    }
+
+    #[test]
+    fn test_take_while_peek() {
+        let a = [1, 2, 3, 10, 4];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        let mut sum = 0;
+        for x in it.take_while_peek(|&&x| x < 10) {
+            sum += x;
+        }
+        assert_eq!(sum, 6);
+        assert_eq!(it.peek_bwd(), Some(&&3));
+        assert_eq!(it.peek_fwd(), Some(&&10));
+        assert_eq!(it.next(), Some(&10));
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_repeat() {
+        let mut it = Peekable::<Repeat<i32>, 2, 4>::repeat(7);
+
+        assert_eq!(it.peek_bwd(), None);
+        assert_eq!(it.peek_fwd_nth(0), Some(&7));
+        assert_eq!(it.peek_fwd_nth(1), Some(&7));
+        assert_eq!(it.peek_fwd_nth(3), Some(&7));
+        assert_eq!(it.peek_fwd_nth(4), None);
+
+        assert_eq!(it.next(), Some(7));
+        assert_eq!(it.next(), Some(7));
+        assert_eq!(it.next(), Some(7));
+        assert_eq!(it.peek_bwd_nth(0), Some(&7));
+        assert_eq!(it.peek_bwd_nth(1), Some(&7));
+        assert_eq!(it.peek_bwd_nth(2), None);
+    }
+
+    #[test]
+    fn test_cursor() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        let mut c = it.cursor();
+        assert_eq!(c.current(), None);
+        assert!(!c.move_next());
+        assert!(!c.move_prev());
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&4));
+        assert_eq!(it.bwd_buffered(), 2);
+        assert_eq!(it.fwd_buffered(), 2);
+
+        let mut c = it.cursor();
+        assert_eq!(c.offset(), 0);
+        assert_eq!(c.current(), Some(&&3));
+        assert!(c.move_next());
+        assert_eq!(c.current(), Some(&&4));
+        assert!(!c.move_next());
+        assert_eq!(c.offset(), 1);
+        assert!(c.move_prev());
+        assert!(c.move_prev());
+        assert_eq!(c.offset(), -1);
+        assert_eq!(c.current(), Some(&&2));
+        assert!(c.move_prev());
+        assert_eq!(c.current(), Some(&&1));
+        assert!(!c.move_prev());
+        assert_eq!(c.offset(), -2);
+        assert!(c.move_next());
+        assert!(c.move_next());
+        assert_eq!(c.current(), Some(&&3));
+
+        assert_eq!(it.next(), Some(&3));
+    }
+
+    #[test]
+    fn test_next_back_n() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+        assert_eq!(it.next_back_n(2), 2);
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.next(), None);
+
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+        assert_eq!(it.next_back_n(10), 4);
+        assert_eq!(it.next_back_n(1), 0);
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_peek_fwd_eof() {
+        let a = [1, 2];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(it.peek_fwd_eof(), PeekResult::Item(&&1));
+        assert_eq!(it.peek_fwd_nth_eof(1), PeekResult::Item(&&2));
+        assert_eq!(it.peek_fwd_nth_eof(2), PeekResult::Eof);
+        assert_eq!(it.peek_fwd_nth_eof(4), PeekResult::OverCapacity);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.peek_fwd_eof(), PeekResult::Eof);
+    }
+
+    #[test]
+    fn test_advance_and_peek() {
+        let a = [1, 2, 3, 4];
+
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+        assert_eq!(it.advance_and_peek(0), Some(&&1));
+        assert_eq!(it.advance_and_peek(2), Some(&&3));
+        assert_eq!(it.peek_bwd_nth(0), Some(&&2));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&1));
+
+        // Advance to the exact end.
+        assert_eq!(it.advance_and_peek(2), None);
+        assert_eq!(it.peek_bwd(), Some(&&4));
+        assert_eq!(it.next(), None);
+
+        // Advance past the end.
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+        assert_eq!(it.advance_and_peek(10), None);
+        assert_eq!(it.peek_bwd(), Some(&&4));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_chain() {
+        let a = [1, 2, 3];
+        let b = [4, 5];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&3));
+
+        let mut it = it.chain(&b);
+        assert_eq!(it.peek_bwd(), Some(&&1));
+        assert_eq!(it.peek_fwd_nth(0), Some(&&2));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&3));
+        assert_eq!(it.peek_fwd_nth(2), Some(&&4));
+        assert_eq!(it.peek_fwd_nth(3), Some(&&5));
+        assert_eq!(it.peek_fwd_nth(4), None);
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&3));
+        assert_eq!(it.next(), Some(&5));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_fwd_buffered_and_fresh() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        assert_eq!(it.fwd_buffered_and_fresh(0), (false, Some(&&1)));
+        assert_eq!(it.fwd_buffered_and_fresh(1), (false, Some(&&2)));
+        assert_eq!(it.fwd_buffered_and_fresh(0), (true, Some(&&1)));
+        assert_eq!(it.fwd_buffered_and_fresh(1), (true, Some(&&2)));
+        assert_eq!(it.fwd_buffered_and_fresh(2), (false, None));
+        assert_eq!(it.fwd_buffered(), 2);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.fwd_buffered_and_fresh(0), (true, Some(&&2)));
+        assert_eq!(it.fwd_buffered_and_fresh(1), (false, Some(&&3)));
+    }
+
+    #[test]
+    fn test_max_rewind() {
+        let a = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut it = Peekable::<Iter<i32>, 4, 4>::new(&a);
+
+        assert_eq!(it.max_rewind(), 0);
+        assert_eq!(it.step_back_by(1), 0);
+
+        assert_eq!(it.advance_and_peek(4), Some(&&5));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&6));
+        assert_eq!(it.bwd_buffered(), 4);
+        assert_eq!(it.fwd_buffered(), 2);
+        assert_eq!(it.max_rewind(), 2);
+
+        assert_eq!(it.step_back_by(10), 2);
+        assert_eq!(it.max_rewind(), 0);
+        assert_eq!(it.peek_bwd(), Some(&&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.next(), Some(&5));
+    }
+
+    #[test]
+    fn test_peek_fwd_nth_budget() {
+        let a = [1, 2, 3, 4, 5];
+        let count = core::cell::Cell::new(0);
+        let mut it = Peekable::<Counting<Iter<i32>>, 2, 4>::new(Counting {
+            iter: a.iter(),
+            count: &count,
+        });
+
+        assert_eq!(it.peek_fwd_nth_budget(2, 2), None);
+        assert_eq!(count.get(), 0);
+        assert_eq!(it.peek_fwd_nth_budget(1, 2), Some(&&2));
+        assert_eq!(count.get(), 2);
+        assert_eq!(it.peek_fwd_nth_budget(1, 0), Some(&&2));
+        assert_eq!(count.get(), 2);
+        assert_eq!(it.peek_fwd_nth_budget(3, 1), None);
+        assert_eq!(count.get(), 2);
+        assert_eq!(it.peek_fwd_nth_budget(3, 2), Some(&&4));
+        assert_eq!(count.get(), 4);
+        assert_eq!(it.peek_fwd_nth_budget(4, 100), None);
+        assert_eq!(count.get(), 4);
+    }
+
+    /// Simple FNV-1a [Hasher] for testing.
+    struct Fnv(u64);
+
+    impl Hasher for Fnv {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for b in bytes {
+                self.0 = (self.0 ^ u64::from(*b)).wrapping_mul(0x100000001b3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_with_hasher() {
+        let a = [1, 2, 3, 4];
+        let mut it1 = Peekable::<Iter<i32>, 2, 2>::with_hasher(&a, Fnv(0xcbf29ce484222325));
+        let mut it2 = Peekable::<Iter<i32>, 2, 2>::with_hasher(&a, Fnv(0xcbf29ce484222325));
+
+        let initial = it1.consumed_hash();
+        assert_eq!(it1.peek_fwd_nth(1), Some(&&2));
+        assert_eq!(it1.consumed_hash(), initial);
+
+        assert_eq!(it1.next(), Some(&1));
+        let after_one = it1.consumed_hash();
+        assert_ne!(after_one, initial);
+        assert_eq!(it1.peek(), Some(&&2));
+        assert_eq!(it1.peek_bwd(), Some(&&1));
+        assert_eq!(it1.consumed_hash(), after_one);
+
+        assert_eq!(it1.by_ref().count(), 3);
+        assert_eq!(it2.by_ref().count(), 4);
+        assert_eq!(it1.consumed_hash(), it2.consumed_hash());
+    }
+
+    #[test]
+    fn test_flatten_peekable() {
+        let a: [&[i32]; 4] = [&[1, 2], &[], &[3], &[4, 5]];
+        let mut it =
+            Peekable::<Copied<Iter<&[i32]>>, 2, 4>::new(a.iter().copied()).flatten_peekable();
+
+        assert_eq!(it.peek_fwd_nth(0), Some(&&1));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+        assert_eq!(it.peek_fwd_nth(2), Some(&&3));
+        assert_eq!(it.peek_fwd_nth(3), Some(&&4));
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.peek_bwd_nth(0), Some(&&3));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&2));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&5));
+        assert_eq!(it.peek_fwd_nth(2), None);
+
+        let mut it = Peekable::<Copied<Iter<&[i32]>>, 2, 4>::new(a.iter().copied());
+        it.set_track_bwd(false);
+        let mut it = it.flatten_peekable();
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_bwd(), None);
+    }
+
+    #[test]
+    fn test_flatten_peekable_after_peek() {
+        let a: [&[i32]; 4] = [&[1, 2], &[3], &[4], &[5, 6]];
+        let mut it = Peekable::<Copied<Iter<&[i32]>>, 2, 2>::new(a.iter().copied());
+        assert_eq!(it.next(), Some(&[1, 2][..]));
+        assert_eq!(it.peek_fwd_nth_deep(2), Some(&&[5, 6][..]));
+        let mut it = it.flatten_peekable();
+        assert_eq!(it.peek_bwd(), None);
+        assert_eq!(it.stream_pos(), 0);
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.next(), Some(&5));
+        assert_eq!(it.next(), Some(&6));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_fwd_window_eq() {
+        let a = [1, 2, 3, 4, 5];
+        let b = [1, 2, 3, 9];
+        let c = [1, 2];
+        let mut ia = Peekable::<Iter<i32>, 2, 8>::new(&a);
+        let mut ib = Peekable::<Iter<i32>, 2, 3>::new(&b);
+        let mut ic = Peekable::<Iter<i32>, 2, 4>::new(&c);
+
+        // Matching window, clamped to the smaller FWD_SIZE.
+        assert!(ia.fwd_window_eq(&mut ib, 10));
+        assert!(ib.fwd_window_eq(&mut ia, 3));
+        assert!(ia.fwd_window_eq(&mut ic, 2));
+        // Different remaining lengths.
+        assert!(!ia.fwd_window_eq(&mut ic, 3));
+        let mut ic2 = Peekable::<Iter<i32>, 2, 4>::new(&c);
+        assert!(ic.fwd_window_eq(&mut ic2, 4));
+
+        // Diverging window.
+        assert_eq!(ib.next(), Some(&1));
+        assert!(!ia.fwd_window_eq(&mut ib, 3));
+        assert_eq!(ia.next(), Some(&1));
+        assert!(ia.fwd_window_eq(&mut ib, 2));
+        assert!(!ia.fwd_window_eq(&mut ib, 3));
+        assert_eq!(ia.peek_bwd(), Some(&&1));
+    }
+
+    #[test]
+    fn test_collect_fwd_into() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        // Smaller than the buffered window.
+        assert_eq!(it.peek_fwd_nth(3), Some(&&4));
+        let mut out = [&0; 2];
+        assert_eq!(it.collect_fwd_into(&mut out), 2);
+        assert_eq!(out, [&1, &2]);
+        assert_eq!(it.peek_bwd(), Some(&&2));
+        assert_eq!(it.fwd_buffered(), 2);
+
+        // Larger than the remaining elements.
+        let mut out = [&0; 8];
+        assert_eq!(it.collect_fwd_into(&mut out), 3);
+        assert_eq!(out[..3], [&3, &4, &5]);
+        assert_eq!(out[3], &0);
+        assert_eq!(it.peek_bwd_nth(0), Some(&&5));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&4));
+        assert_eq!(it.collect_fwd_into(&mut out), 0);
+    }
+
+    #[test]
+    fn test_fwd_find_map() {
+        let a = [1, 3, 4, 5, 6];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(
+            it.fwd_find_map(|&&x| if x % 2 == 0 { Some(x * 10) } else { None }),
+            Some((2, 40))
+        );
+        assert_eq!(
+            it.fwd_find_map(|&&x| if x > 5 { Some(x) } else { None }),
+            None
+        );
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(
+            it.fwd_find_map(|&&x| if x > 5 { Some(x) } else { None }),
+            Some((3, 6))
+        );
+    }
+
+    #[test]
+    fn test_next_zip() {
+        let a = [1, 2, 3];
+        let b = [10, 20];
+        let mut ia = Peekable::<Iter<i32>, 2, 2>::new(&a);
+        let mut ib = Peekable::<Iter<i32>, 2, 2>::new(&b);
+
+        assert_eq!(ia.next_zip(&mut ib), Some((&1, &10)));
+        assert_eq!(ia.peek(), Some(&&2));
+        assert_eq!(ib.peek(), Some(&&20));
+        assert_eq!(ia.next_zip(&mut ib), Some((&2, &20)));
+        assert_eq!(ia.next_zip(&mut ib), None);
+        assert_eq!(ia.peek(), Some(&&3));
+        assert_eq!(ia.peek_bwd(), Some(&&2));
+        assert_eq!(ib.peek(), None);
+        assert_eq!(ib.peek_bwd_nth(1), Some(&&10));
+    }
+
+    #[test]
+    fn test_bwd_is_full() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 3, 2>::new(&a);
+
+        assert!(!it.bwd_is_full());
+        assert_eq!(it.next(), Some(&1));
+        assert!(!it.bwd_is_full());
+        assert_eq!(it.next(), Some(&2));
+        assert!(!it.bwd_is_full());
+        assert_eq!(it.next(), Some(&3));
+        assert!(it.bwd_is_full());
+        assert_eq!(it.next(), Some(&4));
+        assert!(it.bwd_is_full());
+    }
+
+    #[test]
+    fn test_fwd_is_full() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 2, 3>::new(&a);
+        assert!(!it.fwd_is_full());
+        assert_eq!(it.fill_fwd(), 3);
+        assert!(it.fwd_is_full());
+        assert_eq!(it.next(), Some(&1));
+        assert!(!it.fwd_is_full());
+
+        let a = [1, 2];
+        let mut it = Peekable::<Iter<i32>, 2, 3>::new(&a);
+        assert_eq!(it.fill_fwd(), 2);
+        assert!(!it.fwd_is_full());
+    }
+
+    #[test]
+    fn test_scan_la() {
+        let a = [1, 4, 9, 16, 25];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 2>::new(a.iter().copied()).scan_la(
+            0,
+            |count, cur, next| {
+                *count += 1;
+                next.map(|next| (*count, next - cur))
+            },
+        );
+        assert_eq!(it.next(), Some((1, 3)));
+        assert_eq!(it.next(), Some((2, 5)));
+        assert_eq!(it.next(), Some((3, 7)));
+        assert_eq!(it.next(), Some((4, 9)));
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_dedup_bwd() {
+        let a = [1, 1, 2, 3, 3, 3, 2];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a).dedup_bwd();
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), None);
+
+        // The variants differ at the start, if an element has been consumed before.
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+        assert_eq!(it.next(), Some(&1));
+        let mut it = it.dedup_bwd();
+        assert_eq!(it.next(), Some(&2));
+
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+        assert_eq!(it.next(), Some(&1));
+        let mut it = it.dedup();
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+    }
+
+    #[test]
+    fn test_remaining() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        assert_eq!(it.remaining(), Some(5));
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.remaining(), Some(4));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&3));
+        assert_eq!(it.remaining(), Some(4));
+        assert_eq!(it.advance_and_peek(4), None);
+        assert_eq!(it.remaining(), Some(0));
+    }
+
+    #[test]
+    fn test_peek_fwd_nth_at() {
+        let a = [10, 11, 12, 13];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        assert_eq!(it.stream_pos(), 0);
+        assert_eq!(it.peek_fwd_nth_at(0), Some((0, &&10)));
+        assert_eq!(it.peek_fwd_nth_at(1), Some((1, &&11)));
+        assert_eq!(it.next(), Some(&10));
+        assert_eq!(it.stream_pos(), 1);
+        assert_eq!(it.peek_fwd_nth_at(0), Some((1, &&11)));
+        assert_eq!(it.peek_fwd_nth_at(1), Some((2, &&12)));
+        assert_eq!(it.advance_and_peek(2), Some(&&13));
+        assert_eq!(it.stream_pos(), 3);
+        assert_eq!(it.peek_fwd_nth_at(0), Some((3, &&13)));
+        assert_eq!(it.peek_fwd_nth_at(1), None);
+        assert!(it.step_back());
+        assert_eq!(it.stream_pos(), 2);
+        assert_eq!(it.peek_fwd_nth_at(0), Some((2, &&12)));
+        assert_eq!(it.advance_and_peek(5), None);
+        assert_eq!(it.stream_pos(), 4);
+    }
+
+    #[test]
+    fn test_peek_fwd_nth_result() {
+        let a = [1, 2];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(it.peek_fwd_nth_result(0), Ok(&&1));
+        assert_eq!(it.peek_fwd_nth_result(1), Ok(&&2));
+        assert_eq!(it.peek_fwd_nth_result(2), Err(PeekError::Exhausted));
+        assert_eq!(
+            it.peek_fwd_nth_result(4),
+            Err(PeekError::OverCapacity {
+                requested: 4,
+                capacity: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_track_bwd() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        assert_eq!(it.next(), Some(&1));
+        it.set_track_bwd(false);
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.peek_bwd(), Some(&&1));
+        assert_eq!(it.stream_pos(), 3);
+
+        it.set_track_bwd(true);
+        assert_eq!(it.peek_bwd(), None);
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.peek_bwd_nth(0), Some(&&4));
+        assert_eq!(it.peek_bwd_nth(1), None);
+    }
+
+    #[test]
+    fn test_set_track_bwd_rewind() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        assert_eq!(it.next(), Some(&1));
+        it.set_track_bwd(false);
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.max_rewind(), 0);
+        assert!(!it.step_back());
+        assert_eq!(it.step_back_by(2), 0);
+        assert!(!it.rewind_to(2));
+        assert_eq!(it.stream_pos(), 3);
+        assert_eq!(it.next(), Some(&4));
+
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+        it.set_track_bwd(false);
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.transaction().rollback(), Ok(()));
+        it.set_track_bwd(true);
+        assert_eq!(it.next(), Some(&2));
+        assert!(it.step_back());
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+    }
+
+    #[test]
+    fn test_peek_fwd_map_array() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(it.peek_fwd_map_array(|&&x| x * 2), Some([2, 4, 6]));
+        assert_eq!(it.peek_fwd_map_array::<_, 0, _>(|&&x| x), Some([]));
+        assert_eq!(it.peek_fwd_map_array::<_, 4, _>(|&&x| x), None);
+        assert_eq!(it.peek_fwd_map_array::<_, 5, _>(|&&x| x), None);
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_fwd_map_array(|&&x| x), Some([2, 3]));
+        assert_eq!(it.peek_fwd_map_array::<_, 3, _>(|&&x| x), None);
+    }
+
+    #[test]
+    fn test_prefetch_saturating() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 2, 3>::new(&a);
+        assert!(it.prefetch_saturating());
+        assert_eq!(it.fwd_buffered(), 3);
+        assert_eq!(it.next(), Some(&1));
+        assert!(it.prefetch_saturating());
+        assert_eq!(it.next(), Some(&2));
+        assert!(!it.prefetch_saturating());
+        assert_eq!(it.fwd_buffered(), 2);
+
+        let a = [1, 2];
+        let mut it = Peekable::<Iter<i32>, 2, 3>::new(&a);
+        assert!(!it.prefetch_saturating());
+        assert_eq!(it.fwd_buffered(), 2);
+    }
+
+    #[test]
+    fn test_matches_bwd() {
+        let a = ['(', 'x', ')', '}'];
+        let mut it = Peekable::<Copied<Iter<char>>, 3, 2>::new(a.iter().copied());
+
+        assert!(it.matches_bwd::<char>(&[]));
+        assert!(!it.matches_bwd(&['(']));
+        assert_eq!(it.advance_and_peek(4), None);
+        assert!(it.matches_bwd(&['}', ')']));
+        assert!(it.matches_bwd(&['}', ')', 'x']));
+        assert!(!it.matches_bwd(&[')', '}']));
+        assert!(!it.matches_bwd(&['}', ')', 'x', '(']));
+    }
+
+    #[test]
+    fn test_pop_bwd() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 3, 2>::new(&a);
+
+        assert_eq!(it.pop_bwd(), None);
+        assert_eq!(it.advance_and_peek(2), Some(&&3));
+        assert_eq!(it.pop_bwd(), Some(&2));
+        assert_eq!(it.bwd_buffered(), 1);
+        assert_eq!(it.peek_bwd(), Some(&&1));
+        assert_eq!(it.stream_pos(), 2);
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.peek_bwd_nth(0), Some(&&3));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&1));
+        assert_eq!(it.pop_bwd(), Some(&3));
+        assert_eq!(it.pop_bwd(), Some(&1));
+        assert_eq!(it.pop_bwd(), None);
+        assert_eq!(it.next(), Some(&4));
+    }
+
+    #[test]
+    fn test_pop_bwd_rewind() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        // The element before the forgotten one can't be un-consumed.
+        it.next();
+        it.next();
+        assert_eq!(it.pop_bwd(), Some(&2));
+        assert_eq!(it.max_rewind(), 0);
+        assert!(!it.step_back());
+        assert!(!it.rewind_to(1));
+        assert_eq!(it.stream_pos(), 2);
+        assert_eq!(it.next(), Some(&3));
+
+        // Elements consumed after the pop can be un-consumed.
+        assert_eq!(it.max_rewind(), 1);
+        assert!(it.step_back());
+        assert!(!it.step_back());
+        assert_eq!(it.next(), Some(&3));
+        let mut t = it.transaction();
+        assert_eq!(t.next(), Some(&4));
+        assert_eq!(t.rollback(), Ok(()));
+        assert_eq!(it.stream_pos(), 3);
+        assert_eq!(it.step_back_by(2), 1);
+        assert_eq!(it.stream_pos(), 2);
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_flat_map_peek() {
+        let a = [2, 0, 3];
+        let mut it =
+            Peekable::<Copied<Iter<i32>>, 2, 4>::new(a.iter().copied()).flat_map_peek(|n| 0..n);
+
+        assert_eq!(it.peek_fwd_nth(0), Some(&0));
+        assert_eq!(it.peek_fwd_nth(1), Some(&1));
+        assert_eq!(it.peek_fwd_nth(2), Some(&0));
+        assert_eq!(it.peek_fwd_nth(3), Some(&1));
+        assert_eq!(it.next(), Some(0));
+        assert_eq!(it.next(), Some(1));
+        assert_eq!(it.next(), Some(0));
+        assert_eq!(it.peek_bwd_nth(1), Some(&1));
+        assert_eq!(it.peek_fwd_nth(1), Some(&2));
+        assert_eq!(it.peek_fwd_nth(2), None);
+    }
+
+    #[test]
+    fn test_flat_map_peek_after_peek() {
+        let a = [2, 0, 3];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 4>::new(a.iter().copied());
+        assert_eq!(it.peek_fwd_nth(1), Some(&0));
+        let mut it = it.flat_map_peek(|n| 0..n);
+        assert_eq!(it.peek_fwd_nth(2), Some(&0));
+        assert!(it.by_ref().eq([0, 1, 0, 1, 2]));
+    }
+
+    #[test]
+    fn test_debug_invariants() {
+        let a = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut it = Peekable::<Iter<i32>, 3, 3>::new(&a);
+
+        it.debug_invariants();
+        assert_eq!(it.fill_fwd(), 3);
+        it.debug_invariants();
+        assert_eq!(it.advance_and_peek(4), Some(&&5));
+        it.debug_invariants();
+        assert_eq!(it.step_back_by(2), 2);
+        it.debug_invariants();
+        assert!(!it.rewind_one_if(|_| true));
+        it.debug_invariants();
+        assert_eq!(it.pop_bwd(), Some(&2));
+        it.debug_invariants();
+        assert_eq!(it.by_ref().count(), 6);
+        it.debug_invariants();
+
+        let mut it = Peekable::<Iter<i32>, 3, 2>::with_bwd_history(&a, [&7, &0, &9]);
+        it.debug_invariants();
+        it.set_record_sentinel(&0);
+        assert_eq!(it.peek_bwd_nth(0), Some(&&9));
+        assert_eq!(it.peek_bwd_nth(1), None);
+        it.debug_invariants();
+        assert_eq!(it.peek_fwd_nth_deep(3), Some(&&4));
+        it.debug_invariants();
+        it.next();
+        it.debug_invariants();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_debug_invariants_broken() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Iter<i32>, 3, 2>::new(&a);
+        it.next();
+        it.next();
+        it.pos = 1;
+        it.debug_invariants();
+    }
+
+    #[test]
+    fn test_peek_cursor() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        let before = it.peek_cursor();
+        assert_eq!(it.peek(), Some(&&1));
+        let after = it.peek_cursor();
+        assert_eq!(before, after);
+        assert_eq!(after.bwd_buffered(), 0);
+
+        assert_eq!(it.next(), Some(&1));
+        let advanced = it.peek_cursor();
+        assert_ne!(advanced, after);
+        assert!(advanced > after);
+        assert_eq!(advanced.stream_pos(), 1);
+        assert_eq!(advanced.bwd_buffered(), 1);
+
+        // Same position, different history.
+        assert_eq!(it.pop_bwd(), Some(&1));
+        let popped = it.peek_cursor();
+        assert_eq!(popped.stream_pos(), advanced.stream_pos());
+        assert_ne!(popped, advanced);
+        assert!(popped < advanced);
+    }
+
+    #[test]
+    fn test_next_if_prev() {
+        let a = ['a', '\\', 'n', 'n'];
+        let mut it = Peekable::<Copied<Iter<char>>, 2, 2>::new(a.iter().copied());
+
+        // Stream start.
+        assert_eq!(it.next_if_prev(|prev, _| prev.is_some()), None);
+        assert_eq!(
+            it.next_if_prev(|prev, &next| prev.is_none() && next == 'a'),
+            Some('a')
+        );
+
+        // Mid-stream.
+        assert_eq!(it.next(), Some('\\'));
+        let escaped = |prev: Option<&char>, _: &char| prev == Some(&'\\');
+        assert_eq!(it.next_if_prev(escaped), Some('n'));
+        assert_eq!(it.next_if_prev(escaped), None);
+        assert_eq!(it.next(), Some('n'));
+        assert_eq!(it.next_if_prev(|_, _| true), None);
+    }
+
+    #[test]
+    fn test_limit() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&3));
+        let mut it = it.limit(3);
+        assert_eq!(it.peek_bwd(), Some(&&1));
+        assert_eq!(it.peek_fwd_nth(2), Some(&&4));
+        assert_eq!(it.peek_fwd_nth(3), None);
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.next(), None);
+
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+        assert_eq!(it.fill_fwd(), 4);
+        let mut it = it.limit(2);
+        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+        assert_eq!(it.peek_fwd_nth(2), None);
+        assert_eq!(it.by_ref().count(), 2);
+    }
+
+    #[test]
+    fn test_expect() {
+        let a = ['(', 'x'];
+        let mut it = Peekable::<Copied<Iter<char>>, 2, 2>::new(a.iter().copied());
+
+        assert_eq!(it.expect(&'('), Ok('('));
+        assert_eq!(it.expect(&')'), Err(PeekError::Mismatch { position: 1 }));
+        assert_eq!(it.peek(), Some(&'x'));
+        assert_eq!(it.expect(&'x'), Ok('x'));
+        assert_eq!(it.expect(&'x'), Err(PeekError::Exhausted));
+    }
+
+    #[test]
+    fn test_transaction() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Iter<i32>, 4, 4>::new(&a);
+
+        // Committed.
+        let mut t = it.transaction();
+        assert_eq!(t.next(), Some(&1));
+        assert_eq!(t.next(), Some(&2));
+        t.commit();
+        assert_eq!(it.stream_pos(), 2);
+        assert_eq!(it.peek(), Some(&&3));
+
+        // Rolled back on drop.
+        {
+            let mut t = it.transaction();
+            assert_eq!(t.next(), Some(&3));
+            assert_eq!(t.peek(), Some(&&4));
+            assert_eq!(t.next(), Some(&4));
+            assert_eq!(t.peek_bwd(), Some(&&4));
+        }
+        assert_eq!(it.stream_pos(), 2);
+        assert_eq!(it.peek_bwd(), Some(&&2));
+        assert_eq!(it.next(), Some(&3));
+
+        // Explicit rollback.
+        let mut t = it.transaction();
+        assert_eq!(t.next(), Some(&4));
+        assert_eq!(t.rollback(), Ok(()));
+        assert_eq!(it.next(), Some(&4));
+    }
+
+    #[test]
+    fn test_transaction_over_capacity() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        let mut t = it.transaction();
+        assert_eq!(t.by_ref().count(), 5);
+        assert_eq!(
+            t.rollback(),
+            Err(PeekError::OverCapacity {
+                requested: 5,
+                capacity: 2
+            })
+        );
+        assert_eq!(it.next(), Some(&4));
+
+        // Dropping an over capacity transaction does not panic.
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+        {
+            let mut t = it.transaction();
+            assert_eq!(t.by_ref().count(), 5);
+        }
+        assert_eq!(it.stream_pos(), 3);
+        assert_eq!(it.next(), Some(&4));
+    }
+
+    #[test]
+    fn test_peek_fwd_nth_counting() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(it.peek_fwd_nth_counting(2), (Some(&&3), 3));
+        assert_eq!(it.peek_fwd_nth_counting(2), (Some(&&3), 0));
+        assert_eq!(it.peek_fwd_nth_counting(3), (Some(&&4), 1));
+        assert_eq!(it.peek_fwd_nth_counting(4), (None, 0));
+        assert_eq!(it.advance_and_peek(3), Some(&&4));
+        assert_eq!(it.peek_fwd_nth_counting(1), (Some(&&5), 1));
+        assert_eq!(it.peek_fwd_nth_counting(2), (None, 1));
+    }
+
+    #[test]
+    fn test_into_std_peekable() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&3));
+        let mut it = it.into_std_peekable();
+        assert_eq!(it.peek(), Some(&&2));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.next(), Some(&5));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_fwd_view_until_sentinel() {
+        let a = [1, 2, 0, 3, 4, 5];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 4>::new(a.iter().copied());
+
+        // Sentinel within the window.
+        assert_eq!(it.fwd_view_until_sentinel(0).count(), 2);
+        assert_eq!(it.fwd_view_until_sentinel(0).sum::<i32>(), 3);
+
+        // Sentinel absent.
+        assert_eq!(it.fwd_view_until_sentinel(9).count(), 4);
+
+        // Sentinel at position 0.
+        assert_eq!(it.advance_and_peek(2), Some(&0));
+        assert_eq!(it.fwd_view_until_sentinel(0).count(), 0);
+        assert_eq!(it.stream_pos(), 2);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_chunks() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 2>::new(a.iter().copied()).chunks(3);
+        assert_eq!(it.next(), Some(vec![1, 2, 3]));
+        assert_eq!(it.next(), Some(vec![4, 5, 6]));
+        assert_eq!(it.next(), None);
+
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 2>::new(a.iter().copied()).chunks(4);
+        assert_eq!(it.next(), Some(vec![1, 2, 3, 4]));
+        assert_eq!(it.next(), Some(vec![5, 6]));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_longest_fwd_prefix_match() {
+        let a = ['=', '=', '=', 'x'];
+        let mut it = Peekable::<Copied<Iter<char>>, 2, 2>::new(a.iter().copied());
+
+        let patterns: [&[char]; 4] = [&['='], &['=', '='], &['!', '='], &['=', '=']];
+        assert_eq!(it.longest_fwd_prefix_match(&patterns), Some(1));
+        assert_eq!(it.stream_pos(), 0);
+
+        // Longer than FWD_SIZE.
+        let patterns: [&[char]; 2] = [&['=', '=', '='], &['=']];
+        assert_eq!(it.longest_fwd_prefix_match(&patterns), Some(1));
+
+        assert_eq!(it.advance_and_peek(2), Some(&'='));
+        assert_eq!(it.longest_fwd_prefix_match(&[&['=', '=']]), None);
+        assert_eq!(it.longest_fwd_prefix_match(&[&['!'], &['=', 'x']]), Some(1));
+        assert_eq!(it.longest_fwd_prefix_match::<char>(&[]), None);
+    }
+
+    #[test]
+    fn test_consume_longest_fwd_prefix() {
+        let a = ['=', '=', '=', 'x'];
+        let mut it = Peekable::<Copied<Iter<char>>, 2, 2>::new(a.iter().copied());
+        let patterns: [&[char]; 3] = [&['='], &['=', '='], &['x']];
+
+        assert_eq!(it.consume_longest_fwd_prefix(&patterns), Some(1));
+        assert_eq!(it.stream_pos(), 2);
+        assert_eq!(it.peek_bwd_nth(1), Some(&'='));
+        assert_eq!(it.consume_longest_fwd_prefix(&patterns), Some(0));
+        assert_eq!(it.stream_pos(), 3);
+
+        assert_eq!(it.consume_longest_fwd_prefix(&patterns[..2]), None);
+        assert_eq!(it.stream_pos(), 3);
+        assert_eq!(it.peek(), Some(&'x'));
+        assert_eq!(it.consume_longest_fwd_prefix(&patterns), Some(2));
+        assert_eq!(it.stream_pos(), 4);
+    }
+
+    #[test]
+    fn test_peek_fwd_scan() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        {
+            let mut s = it.peek_fwd_scan(|&&x| x * 10);
+            assert_eq!(s.next(), Some(10));
+            assert_eq!(s.next(), Some(20));
+        }
+        assert_eq!(it.fwd_buffered(), 2);
+        assert_eq!(it.stream_pos(), 0);
+
+        assert_eq!(it.peek_fwd_scan(|&&x| x).count(), 4);
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_fwd_scan(|&&x| x).sum::<i32>(), 14);
+    }
+
+    #[test]
+    fn test_sub() {
+        fn parse_digits(mut s: SubScanner<'_, Copied<Iter<char>>, 2, 2>) -> u32 {
+            let mut value = 0;
+            while let Some(d) = s.peek().and_then(|c| c.to_digit(10)) {
+                value = value * 10 + d;
+                s.next();
+            }
+            value
+        }
+
+        let a = ['1', '2', '3', '+', '4'];
+        let mut it = Peekable::<Copied<Iter<char>>, 2, 2>::new(a.iter().copied());
+
+        assert_eq!(parse_digits(it.sub()), 123);
+        assert_eq!(it.stream_pos(), 3);
+        assert_eq!(it.next(), Some('+'));
+        let mut s = it.sub();
+        assert_eq!(parse_digits(s.sub()), 4);
+        assert_eq!(s.stream_pos(), 5);
+        assert_eq!(it.peek_bwd(), Some(&'4'));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_consume_run() {
+        let a = [7, 7, 7, 7, 7, 7, 1, 2];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        assert_eq!(it.consume_run(|a, b| a == b), 6);
+        assert_eq!(it.stream_pos(), 6);
+        assert_eq!(it.peek_bwd(), Some(&&7));
+        assert_eq!(it.consume_run(|a, b| a == b), 1);
+        assert_eq!(it.peek(), Some(&&2));
+        assert_eq!(it.consume_run(|a, b| a == b), 1);
+        assert_eq!(it.consume_run(|a, b| a == b), 0);
+    }
+
+    #[test]
+    fn test_with_previous() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 1>::new(a.iter().copied()).with_previous();
+        assert_eq!(it.next(), Some((None, 1)));
+        assert_eq!(it.next(), Some((Some(1), 2)));
+        assert_eq!(it.next(), Some((Some(2), 3)));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_commit_window_if() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        // Commit none.
+        assert_eq!(
+            it.commit_window_if(3, |w| {
+                assert_eq!(w, [&&1, &&2, &&3]);
+                None
+            }),
+            0
+        );
+        assert_eq!(it.stream_pos(), 0);
+
+        // Commit partial.
+        assert_eq!(
+            it.commit_window_if(3, |w| w.iter().position(|&&&x| x == 2)),
+            1
+        );
+        assert_eq!(it.stream_pos(), 1);
+
+        // Commit all, clamped to the window.
+        assert_eq!(
+            it.commit_window_if(10, |w| {
+                assert_eq!(w.len(), 4);
+                Some(100)
+            }),
+            4
+        );
+        assert_eq!(it.stream_pos(), 5);
+        assert_eq!(it.peek_bwd(), Some(&&5));
+
+        // Short stream.
+        assert_eq!(it.commit_window_if(3, |w| Some(w.len())), 1);
+        assert_eq!(
+            it.commit_window_if(3, |w| {
+                assert!(w.is_empty());
+                Some(1)
+            }),
+            0
+        );
+    }
+
+    #[test]
+    fn test_on_consume() {
+        let a = [1, 2, 3, 4];
+        let mut count = 0;
+        let mut sum = 0;
+        {
+            let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a).on_consume(|&&x| {
+                count += 1;
+                sum += x;
+            });
+            assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+            assert_eq!(it.next(), Some(&1));
+            assert_eq!(it.next(), Some(&2));
+            assert_eq!(it.peek_bwd(), Some(&&2));
+            assert_eq!(it.stream_pos(), 2);
+            assert_eq!(it.by_ref().count(), 2);
+        }
+        assert_eq!(count, 4);
+        assert_eq!(sum, 10);
+    }
+
+    #[test]
+    fn test_peek_fwd_with_context() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        assert_eq!(it.peek_fwd_with_context(0), None);
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_fwd_with_context(0), Some((&&1, &&2)));
+        assert_eq!(it.peek_fwd_with_context(1), Some((&&1, &&3)));
+        assert_eq!(it.peek_fwd_with_context(2), None);
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.peek_fwd_with_context(0), Some((&&2, &&3)));
+        assert_eq!(it.peek_fwd_with_context(1), None);
+    }
+
+    #[test]
+    fn test_free_capacity() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Iter<i32>, 3, 4>::new(&a);
+
+        it.shrink_to_fit();
+        assert_eq!(it.free_fwd_capacity(), 4);
+        assert_eq!(it.free_bwd_capacity(), 3);
+        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+        assert_eq!(it.free_fwd_capacity(), 2);
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.free_fwd_capacity(), 3);
+        assert_eq!(it.free_bwd_capacity(), 2);
+        assert_eq!(it.fill_fwd(), 4);
+        assert_eq!(it.free_fwd_capacity(), 0);
+        assert_eq!(it.advance_and_peek(3), Some(&&5));
+        assert_eq!(it.free_bwd_capacity(), 0);
+        assert_eq!(it.free_fwd_capacity(), 3);
+    }
+
+    #[test]
+    fn test_from_chars() {
+        let mut it = Peekable::<Chars, 2, 4>::from_chars("fn main");
+
+        assert!(it.peek_fwd_matches_str(""));
+        assert!(it.peek_fwd_matches_str("fn"));
+        assert!(it.peek_fwd_matches_str("fn m"));
+        assert!(!it.peek_fwd_matches_str("fun"));
+        // Longer than FWD_SIZE.
+        assert!(!it.peek_fwd_matches_str("fn ma"));
+        assert_eq!(it.stream_pos(), 0);
+        assert_eq!(it.next(), Some('f'));
+        assert!(it.peek_fwd_matches_str("n ma"));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_next_map() {
+        use alloc::string::{String, ToString};
+
+        let a = [1, 2];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 2>::new(a.iter().copied());
+
+        assert_eq!(it.next_map(|x| x.to_string()), Some(String::from("1")));
+        assert_eq!(it.peek_bwd(), Some(&1));
+        assert_eq!(
+            it.next_map(|x| x.to_string() + "!"),
+            Some(String::from("2!"))
+        );
+        assert_eq!(it.peek_bwd(), Some(&2));
+        assert_eq!(it.next_map(|x| x.to_string()), None);
+    }
+
+    #[test]
+    fn test_fill_fwd_while() {
+        let a = [1, 2, 0, 3, 4, 5, 6, 7];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        // Stops early.
+        assert_eq!(it.fill_fwd_while(|&&x| x != 0), 3);
+        assert_eq!(it.peek_fwd_nth_counting(2), (Some(&&0), 0));
+
+        // Fills to capacity.
+        assert_eq!(it.advance_and_peek(3), Some(&&3));
+        assert_eq!(it.fill_fwd_while(|&&x| x != 0), 4);
+        assert_eq!(it.fill_fwd_while(|_| true), 4);
+        assert_eq!(it.next(), Some(&3));
+    }
+
+    #[test]
+    fn test_next_interleaved() {
+        let a = [1, 3, 5];
+        let b = [2, 4];
+        let mut ia = Peekable::<Iter<i32>, 2, 2>::new(&a);
+        let mut ib = Peekable::<Iter<i32>, 2, 2>::new(&b);
+
+        let mut first = true;
+        let mut out = [0; 5];
+        for x in out.iter_mut() {
+            *x = *ia.next_interleaved(&mut ib, &mut first).unwrap();
+        }
+        assert_eq!(out, [1, 2, 3, 4, 5]);
+        assert_eq!(ia.next_interleaved(&mut ib, &mut first), None);
+        assert_eq!(ia.peek_bwd(), Some(&&5));
+        assert_eq!(ib.peek_bwd(), Some(&&4));
+    }
+
+    #[test]
+    fn test_fwd_is_sorted() {
+        let a = [1.0, 2.0, 2.0, 3.0, 1.0, f64::NAN, 5.0];
+        let mut it = Peekable::<Iter<f64>, 2, 4>::new(&a);
+
+        assert!(it.fwd_is_sorted());
+        assert_eq!(it.next(), Some(&1.0));
+        assert!(!it.fwd_is_sorted());
+        assert_eq!(it.advance_and_peek(3), Some(&&1.0));
+        assert!(!it.fwd_is_sorted());
+        assert_eq!(it.advance_and_peek(2), Some(&&5.0));
+        // Single element window.
+        assert!(it.fwd_is_sorted());
+        assert_eq!(it.next(), Some(&5.0));
+        assert!(it.fwd_is_sorted());
+    }
+
+    #[test]
+    fn test_fwd_remaining_buffered_from() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Iter<i32>, 2, 4>::new(&a);
+
+        assert_eq!(it.fwd_remaining_buffered_from(0), 0);
+        assert_eq!(it.peek_fwd_nth(2), Some(&&3));
+        assert_eq!(it.fwd_remaining_buffered_from(0), 3);
+        assert_eq!(it.fwd_remaining_buffered_from(1), 2);
+        assert_eq!(it.fwd_remaining_buffered_from(3), 0);
+        assert_eq!(it.fwd_remaining_buffered_from(10), 0);
+        assert_eq!(it.fwd_buffered(), 3);
+    }
+
+    #[test]
+    fn test_peek_fwd_nth_deep() {
+        let a = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut it = Peekable::<Iter<i32>, 3, 2>::new(&a);
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.free_bwd_capacity(), 2);
+
+        // Normal capacity.
+        assert_eq!(it.peek_fwd_nth_deep(1), Some(&&3));
+        // Borrowed capacity.
+        assert_eq!(it.peek_fwd_nth_deep(2), Some(&&4));
+        assert_eq!(it.peek_fwd_nth_deep(3), Some(&&5));
+        assert_eq!(it.peek_fwd_nth_deep(4), None);
+        assert_eq!(it.free_bwd_capacity(), 0);
+        assert_eq!(it.bwd_buffered(), 1);
+        assert_eq!(it.peek_bwd_nth(1), None);
+        assert_eq!(it.peek_fwd_nth(2), None);
+        it.debug_invariants();
+
+        // Consuming hands back the borrowed slots.
+        assert_eq!(it.next(), Some(&2));
+        it.debug_invariants();
+        assert_eq!(it.peek_bwd_nth(0), Some(&&2));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&1));
+        assert_eq!(it.peek_fwd_nth(1), Some(&&4));
+        assert_eq!(it.peek_fwd_nth_deep(2), Some(&&5));
+        assert_eq!(it.remaining(), Some(6));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), Some(&4));
+        it.debug_invariants();
+        assert_eq!(it.peek_bwd_nth(2), Some(&&2));
+        assert_eq!(it.free_bwd_capacity(), 0);
+        assert_eq!(it.peek_fwd_nth_deep(2), None);
+
+        let rest = it.into_std_peekable();
+        assert_eq!(rest.count(), 4);
+    }
+
+    #[test]
+    fn test_peek_fwd_nth_deep_convert() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let mut it = Peekable::<Iter<i32>, 4, 2>::new(&a);
+
+        assert_eq!(it.peek_fwd_nth_deep(3), Some(&&4));
+        let mut rest = it.into_std_peekable();
+        assert_eq!(rest.next(), Some(&1));
+        assert_eq!(rest.next(), Some(&2));
+        assert_eq!(rest.next(), Some(&3));
+        assert_eq!(rest.next(), Some(&4));
+        assert_eq!(rest.next(), Some(&5));
+
+        let mut it = Peekable::<Iter<i32>, 4, 2>::new(&a);
+        assert_eq!(it.peek_fwd_nth_deep(3), Some(&&4));
+        let mut it = it.limit(3);
+        it.debug_invariants();
+        assert_eq!(it.by_ref().count(), 3);
+        assert_eq!(it.peek_bwd(), Some(&&3));
+
+        let mut it = Peekable::<Iter<i32>, 4, 2>::new(&a);
+        assert_eq!(it.peek_fwd_nth_deep(3), Some(&&4));
+        assert!(!it.step_back());
+        assert_eq!(it.pop_bwd(), None);
+        let mut it = it.dedup();
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.next(), Some(&5));
+    }
+
+    #[test]
+    fn test_try_parse() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Copied<Iter<i32>>, 4, 4>::new(a.iter().copied());
+
+        // Failing alternative rewinds completely.
+        let r = it.try_parse(|it| {
+            it.next()?;
+            it.next()?;
+            it.expect(&9).ok()
+        });
+        assert_eq!(r, None);
+        assert_eq!(it.stream_pos(), 0);
+        assert_eq!(it.peek_fwd(), Some(&1));
+
+        // Successful alternative keeps the consumption.
+        let r = it.try_parse(|it| {
+            let x = it.next()?;
+            let y = it.expect(&2).ok()?;
+            Some(x + y)
+        });
+        assert_eq!(r, Some(3));
+        assert_eq!(it.stream_pos(), 2);
+        assert_eq!(it.peek_fwd(), Some(&3));
+        assert_eq!(it.peek_bwd(), Some(&2));
+
+        let r: Option<()> = it.try_parse(|it| {
+            it.by_ref().for_each(drop);
+            None
+        });
+        assert_eq!(r, None);
+        assert_eq!(it.stream_pos(), 2);
+        assert_eq!(it.next(), Some(3));
+        assert_eq!(it.next(), Some(4));
+        assert_eq!(it.next(), Some(5));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_try_parse_over_capacity() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 4>::new(a.iter().copied());
+
+        // The failing alternative consumes more than the history holds.
+        let r: Option<()> = it.try_parse(|it| {
+            it.next()?;
+            it.next()?;
+            it.next()?;
+            None
+        });
+        assert_eq!(r, None);
+        assert_eq!(it.stream_pos(), 3);
+        assert_eq!(it.peek_bwd(), Some(&3));
+        assert_eq!(it.next(), Some(4));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_bwd_context_string() {
+        let mut it = Peekable::<core::ops::Range<u32>, 4, 2>::new(10..30);
+
+        assert_eq!(it.bwd_context_string(3), "");
+        it.next();
+        it.next();
+        assert_eq!(it.bwd_context_string(3), "10 11");
+        assert_eq!(it.bwd_context_string(0), "...");
+        for _ in 0..8 {
+            it.next();
+        }
+        assert_eq!(it.bwd_context_string(3), "... 17 18 19");
+        assert_eq!(it.bwd_context_string(9), "... 16 17 18 19");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_fwd_context_string() {
+        let mut it = Peekable::<core::ops::Range<u32>, 4, 4>::new(10..25);
+
+        for _ in 0..10 {
+            it.next();
+        }
+        let bwd = it.bwd_context_string(2);
+        let fwd = it.fwd_context_string(3);
+        assert_eq!(bwd, "... 18 19");
+        assert_eq!(fwd, "20 21 22 ...");
+        assert_eq!(
+            alloc::format!("{bwd} [^] {fwd}"),
+            "... 18 19 [^] 20 21 22 ..."
+        );
+        assert_eq!(it.stream_pos(), 10);
+        assert_eq!(it.fwd_context_string(9), "20 21 22 23 ...");
+
+        assert_eq!(it.nth(1), Some(21));
+        assert_eq!(it.fwd_context_string(3), "22 23 24");
+        assert_eq!(it.fwd_context_string(0), "...");
+        it.by_ref().for_each(drop);
+        assert_eq!(it.fwd_context_string(3), "");
+    }
+
+    #[test]
+    fn test_runs() {
+        let a = [1, 1, 1, 2, 3, 3];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 1>::new(a.iter().copied()).runs();
+
+        assert_eq!(it.next(), Some((1, 3)));
+        assert_eq!(it.next(), Some((2, 1)));
+        assert_eq!(it.next(), Some((3, 2)));
+        assert_eq!(it.next(), None);
+
+        let a: [i32; 0] = [];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 1>::new(a.iter().copied()).runs();
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_unget() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 2>::new(a.iter().copied());
+
+        assert_eq!(it.unget(9), Ok(()));
+        assert_eq!(it.peek_fwd(), Some(&9));
+        assert_eq!(it.peek_fwd_nth(1), Some(&1));
+        assert!(it.fwd_is_full());
+
+        // Full: The item is handed back and the back element is not evicted.
+        assert_eq!(it.unget(8), Err(8));
+        assert_eq!(it.peek_fwd(), Some(&9));
+        assert_eq!(it.peek_fwd_nth(1), Some(&1));
+        it.debug_invariants();
+
+        assert_eq!(it.next(), Some(9));
+        assert_eq!(it.next(), Some(1));
+        assert_eq!(it.next(), Some(2));
+        assert_eq!(it.next(), Some(3));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_fwd_lcp_len() {
+        let a = [1, 2, 3, 4, 5];
+        let b = [1, 2, 3, 9];
+        let c = [7, 2, 3];
+
+        // Identical prefixes, clamped to the smaller FWD_SIZE.
+        let mut x = Peekable::<Iter<i32>, 1, 4>::new(&a);
+        let mut y = Peekable::<Iter<i32>, 1, 3>::new(&a);
+        assert_eq!(x.fwd_lcp_len(&mut y), 3);
+
+        // Partial common prefix.
+        let mut y = Peekable::<Iter<i32>, 1, 4>::new(&b);
+        assert_eq!(x.fwd_lcp_len(&mut y), 3);
+        assert_eq!(y.fwd_lcp_len(&mut x), 3);
+
+        // Immediately divergent.
+        let mut y = Peekable::<Iter<i32>, 1, 4>::new(&c);
+        assert_eq!(x.fwd_lcp_len(&mut y), 0);
+
+        // Nothing has been consumed.
+        assert_eq!(x.stream_pos(), 0);
+        assert_eq!(x.next(), Some(&1));
+        assert_eq!(y.next(), Some(&7));
+
+        // One stream ends within the window.
+        let mut x = Peekable::<Iter<i32>, 1, 4>::new(&a[..2]);
+        let mut y = Peekable::<Iter<i32>, 1, 4>::new(&b);
+        assert_eq!(x.fwd_lcp_len(&mut y), 2);
+    }
+
+    #[test]
+    fn test_fwd_classify() {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum Quote {
+            Outside,
+            Inside,
+            Escape,
+        }
+
+        let step = |state, c: &char| match (state, *c) {
+            (Quote::Outside, '"') => Quote::Inside,
+            (Quote::Outside, _) => Quote::Outside,
+            (Quote::Inside, '"') => Quote::Outside,
+            (Quote::Inside, '\\') => Quote::Escape,
+            (Quote::Inside, _) => Quote::Inside,
+            (Quote::Escape, _) => Quote::Inside,
+        };
+
+        let mut it = Peekable::<Chars, 1, 6>::from_chars(r#"a "b\"c" d"#);
+        assert_eq!(it.fwd_classify(Quote::Outside, step), Quote::Inside);
+        assert_eq!(it.stream_pos(), 0);
+        assert_eq!(it.nth(4), Some('\\'));
+        assert_eq!(it.fwd_classify(Quote::Escape, step), Quote::Outside);
+        assert_eq!(it.fwd_classify(Quote::Inside, step), Quote::Inside);
+        assert_eq!(it.next(), Some('"'));
+    }
+
+    #[test]
+    fn test_merge_step() {
+        let a = [1, 3, 5, 6];
+        let b = [2, 3, 7];
+        let mut x = Peekable::<Copied<Iter<i32>>, 1, 1>::new(a.iter().copied());
+        let mut y = Peekable::<Copied<Iter<i32>>, 1, 1>::new(b.iter().copied());
+        let mut merge = || x.merge_step(&mut y, |a, b| a.cmp(b));
+
+        assert_eq!(merge(), Some(MergeItem::Left(1)));
+        assert_eq!(merge(), Some(MergeItem::Right(2)));
+        assert_eq!(merge(), Some(MergeItem::Both(3, 3)));
+        assert_eq!(merge(), Some(MergeItem::Left(5)));
+        assert_eq!(merge(), Some(MergeItem::Left(6)));
+        assert_eq!(merge(), Some(MergeItem::Right(7)));
+        assert_eq!(merge(), None);
+        assert_eq!(merge(), None);
+    }
+
+    #[test]
+    fn test_intersperse() {
+        let a: [i32; 0] = [];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 1>::new(a.iter().copied()).intersperse(0);
+        assert_eq!(it.next(), None);
+
+        let a = [1];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 1>::new(a.iter().copied()).intersperse(0);
+        assert_eq!(it.next(), Some(1));
+        assert_eq!(it.next(), None);
+
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 1>::new(a.iter().copied()).intersperse(0);
+        assert_eq!(it.next(), Some(1));
+        assert_eq!(it.next(), Some(0));
+        assert_eq!(it.next(), Some(2));
+        assert_eq!(it.next(), Some(0));
+        assert_eq!(it.next(), Some(3));
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_fwd_glob_match() {
+        let mut it = Peekable::<Chars, 1, 4>::from_chars("abc");
+
+        // Literal.
+        assert!(it.fwd_glob_match("abc"));
+        assert!(!it.fwd_glob_match("ab"));
+        assert!(!it.fwd_glob_match("abd"));
+        assert!(!it.fwd_glob_match("abcd"));
+
+        // Any single char.
+        assert!(it.fwd_glob_match("a?c"));
+        assert!(it.fwd_glob_match("???"));
+        assert!(!it.fwd_glob_match("??"));
+
+        // Any suffix.
+        assert!(it.fwd_glob_match("*"));
+        assert!(it.fwd_glob_match("a*"));
+        assert!(it.fwd_glob_match("?b*"));
+        assert!(it.fwd_glob_match("abc*"));
+        assert!(!it.fwd_glob_match("b*"));
+        assert!(!it.fwd_glob_match("*c"));
+        assert_eq!(it.stream_pos(), 0);
+
+        // Exceeding the window.
+        let mut it = Peekable::<Chars, 1, 4>::from_chars("abcdefgh");
+        assert!(it.fwd_glob_match("abcd*"));
+        assert!(!it.fwd_glob_match("abcde*"));
+        assert!(!it.fwd_glob_match("abcd"));
+        assert!(!it.fwd_glob_match("abcdefgh"));
+        let mut it = Peekable::<Chars, 1, 4>::from_chars("abcd");
+        assert!(!it.fwd_glob_match("abcd"));
+        assert!(it.fwd_glob_match("a??d*"));
+    }
+
+    #[test]
+    fn test_position_fwd_consume_until() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 2>::new(a.iter().copied());
+
+        assert_eq!(it.consume_until(|&x| x == 3), Ok(2));
+        assert_eq!(it.peek_fwd(), Some(&3));
+        assert_eq!(it.peek_bwd(), Some(&2));
+        assert_eq!(it.position_fwd(|&x| x == 4), Ok(1));
+        assert_eq!(it.peek_bwd(), Some(&4));
+        assert_eq!(it.position_fwd(|&x| x == 4), Err(PeekError::Exhausted));
+        assert_eq!(it.consume_until(|&x| x == 4), Err(PeekError::Exhausted));
+    }
+
+    #[test]
+    fn test_set_consume_limit() {
+        let a = [1, 2, 3];
+        let mut it =
+            Peekable::<core::iter::Cycle<Copied<Iter<i32>>>, 2, 2>::new(a.iter().copied().cycle());
+
+        it.set_consume_limit(Some(10));
+        assert_eq!(
+            it.position_fwd(|&x| x == 9),
+            Err(PeekError::LimitReached { limit: 10 })
+        );
+        assert_eq!(it.stream_pos(), 10);
+        assert_eq!(
+            it.consume_until(|&x| x == 9),
+            Err(PeekError::LimitReached { limit: 10 })
+        );
+        assert_eq!(it.stream_pos(), 20);
+        assert_eq!(it.consume_run(|_, _| true), 10);
+        assert_eq!(it.stream_pos(), 30);
+
+        // Matches within the limit are still found.
+        assert_eq!(it.position_fwd(|&x| x == 3), Ok(2));
+        assert_eq!(it.consume_until(|&x| x == 3), Ok(2));
+
+        it.set_consume_limit(Some(0));
+        assert_eq!(it.consume_until(|&x| x == 3), Ok(0));
+        assert_eq!(it.consume_run(|_, _| true), 0);
+        assert_eq!(
+            it.position_fwd(|&x| x == 3),
+            Err(PeekError::LimitReached { limit: 0 })
+        );
+
+        it.set_consume_limit(None);
+        assert_eq!(it.position_fwd(|&x| x == 2), Ok(2));
+    }
+
+    #[test]
+    fn test_fwd_as_slices() {
+        let a = [1, 2, 3, 4, 5, 6, 7];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 4>::new(a.iter().copied());
+
+        assert_eq!(it.fwd_as_slices(), (&[][..], &[][..]));
+        let mut wrapped = false;
+        for start in 1..4 {
+            it.fill_fwd();
+            it.next();
+            it.peek_fwd_nth(3);
+            let (front, back) = it.fwd_as_slices();
+            let mut window = [0; 4];
+            window[..front.len()].copy_from_slice(front);
+            window[front.len()..front.len() + back.len()].copy_from_slice(back);
+            assert_eq!(front.len() + back.len(), 4);
+            assert_eq!(window, [start + 1, start + 2, start + 3, start + 4]);
+            wrapped |= !back.is_empty();
+        }
+        assert!(wrapped);
+    }
+
+    #[test]
+    fn test_bwd_as_slices() {
+        let a = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut it = Peekable::<Copied<Iter<i32>>, 4, 2>::new(a.iter().copied());
+
+        assert_eq!(it.bwd_as_slices(), (&[][..], &[][..]));
+        assert_eq!(it.peek_bwd_iter().next(), None);
+        let mut wrapped = false;
+        for _ in 0..6 {
+            it.next();
+            let (front, back) = it.bwd_as_slices();
+            assert!(front.iter().chain(back).eq(it.peek_bwd_iter()));
+            assert_eq!(front.len() + back.len(), it.bwd_buffered());
+            wrapped |= !back.is_empty();
+        }
+        assert!(wrapped);
+        assert!(it.peek_bwd_iter().copied().eq([6, 5, 4, 3]));
+
+        // Borrowed slots are not part of the history.
+        let mut it = Peekable::<Copied<Iter<i32>>, 4, 2>::new(a.iter().copied());
+        it.next();
+        assert_eq!(it.peek_fwd_nth_deep(4), Some(&6));
+        assert_eq!(it.bwd_as_slices(), (&[1][..], &[][..]));
+        assert!(it.peek_bwd_iter().copied().eq([1]));
+    }
+
+    #[test]
+    fn test_map_while_peek() {
+        let a = [1, 2, -3, 4];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 1>::new(a.iter().copied());
+
+        {
+            let mut m = it.map_while_peek(|&x| u32::try_from(x).ok());
+            assert_eq!(m.next(), Some(1));
+            assert_eq!(m.next(), Some(2));
+            assert_eq!(m.next(), None);
+            assert_eq!(m.next(), None);
+        }
+        assert_eq!(it.peek_bwd(), Some(&2));
+        assert_eq!(it.peek_fwd(), Some(&-3));
+        assert_eq!(it.next(), Some(-3));
+        assert_eq!(it.next(), Some(4));
+    }
+
+    #[test]
+    fn test_peeker() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 4>::new(a.iter().copied());
+
+        {
+            let mut peek = it.peeker();
+            assert_eq!(peek(0), Some(1));
+            assert_eq!(peek(1), Some(2));
+            assert_eq!(peek(2), Some(3));
+            assert_eq!(peek(3), None);
+            assert_eq!(peek(0), Some(1));
+        }
+
+        assert_eq!(it.stream_pos(), 0);
+        assert_eq!(it.next(), Some(1));
+    }
+
+    #[test]
+    fn test_fwd_min_max_by_key() {
+        let a = [3, -1, 4, 1, -4, 5];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 5>::new(a.iter().copied());
+
+        assert_eq!(it.fwd_min_by_key(|&x| x), Some(4));
+        assert_eq!(it.fwd_max_by_key(|&x| x), Some(2));
+        // Ties resolve to the earliest index.
+        assert_eq!(it.fwd_min_by_key(|&x| i32::abs(x)), Some(1));
+        assert_eq!(it.fwd_max_by_key(|&x| i32::abs(x)), Some(2));
+        assert_eq!(it.fwd_max_by_key(|_| 0), Some(0));
+        assert_eq!(it.stream_pos(), 0);
+
+        // The window is limited to FWD_SIZE.
+        assert_eq!(it.nth(3), Some(1));
+        assert_eq!(it.fwd_max_by_key(|&x| x), Some(1));
+        assert_eq!(it.fwd_min_by_key(|&x| x), Some(0));
+
+        it.by_ref().for_each(drop);
+        assert_eq!(it.fwd_min_by_key(|&x| x), None);
+        assert_eq!(it.fwd_max_by_key(|&x| x), None);
+    }
+
+    #[test]
+    fn test_dry_run_consume_until() {
+        let a = [1, 2, 0, 3, 4, 0, 5];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 3>::new(a.iter().copied());
+
+        // Inside the window.
+        assert_eq!(it.dry_run_consume_until(|&x| x == 0), Some(2));
+        assert_eq!(it.stream_pos(), 0);
+        assert_eq!(it.consume_until(|&x| x == 0), Ok(2));
+
+        // At the window boundary.
+        it.next();
+        assert_eq!(it.dry_run_consume_until(|&x| x == 0), Some(2));
+        assert_eq!(it.dry_run_consume_until(|&x| x == 4), Some(1));
+
+        // Beyond the window.
+        assert_eq!(it.dry_run_consume_until(|&x| x == 5), None);
+        assert_eq!(it.stream_pos(), 3);
+
+        // Exhausted without terminator.
+        it.nth(2);
+        assert_eq!(it.dry_run_consume_until(|&x| x == 0), None);
+        assert_eq!(it.next(), Some(5));
+    }
+
+    #[test]
+    fn test_replay_bwd() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Copied<Iter<i32>>, 3, 2>::new(a.iter().copied());
+
+        assert_eq!(it.replay_bwd().next(), None);
+        it.nth(3);
+        assert!(it.replay_bwd().eq([2, 3, 4]));
+        assert!(it.peek_bwd_iter().copied().eq([4, 3, 2]));
+    }
+
+    #[test]
+    fn test_new_cloneable() {
+        #[derive(Debug, PartialEq)]
+        struct NoClone(u32);
+
+        let mut it: Peekable<CloneableIter<core::array::IntoIter<NoClone, 3>>, 2, 2> =
+            Peekable::<Iter<i32>, 2, 2>::new_cloneable([NoClone(1), NoClone(2), NoClone(3)], |x| {
+                NoClone(x.0 + 100)
+            });
+
+        assert_eq!(it.peek_fwd().map(|x| x.get()), Some(&NoClone(1)));
+        assert_eq!(it.next().map(Cloneable::into_inner), Some(NoClone(1)));
+        assert_eq!(it.peek_bwd().map(|x| x.0), Some(101));
+        assert_eq!(it.next().map(|x| x.0), Some(2));
+        assert_eq!(it.peek_bwd_nth(0).map(|x| x.0), Some(102));
+        assert_eq!(it.peek_bwd_nth(1).map(|x| x.0), Some(101));
+        assert_eq!(it.peek_bwd().cloned().map(|x| x.0), Some(202));
+    }
+
+    #[test]
+    fn test_fwd_find_all() {
+        let a = [1, 0, 2, 0, 0, 3, 0];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 6>::new(a.iter().copied());
+        let mut out = [usize::MAX; 8];
+
+        // Multiple matches within the window.
+        assert_eq!(it.fwd_find_all(|&x| x == 0, &mut out), 3);
+        assert_eq!(out[..4], [1, 3, 4, usize::MAX]);
+
+        // No matches.
+        assert_eq!(it.fwd_find_all(|&x| x == 9, &mut out), 0);
+
+        // Undersized output buffer.
+        let mut small = [0; 2];
+        assert_eq!(it.fwd_find_all(|&x| x == 0, &mut small), 2);
+        assert_eq!(small, [1, 3]);
+        assert_eq!(it.fwd_find_all(|_| true, &mut []), 0);
+        assert_eq!(it.stream_pos(), 0);
+    }
+
+    #[test]
+    fn test_bwd_oldest_age() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let mut it = Peekable::<Copied<Iter<i32>>, 3, 1>::new(a.iter().copied());
+
+        assert_eq!(it.bwd_oldest_age(), None);
+        it.next();
+        assert_eq!(it.bwd_oldest_age(), Some(1));
+        it.nth(1);
+        assert_eq!(it.bwd_oldest_age(), Some(3));
+        assert_eq!(it.peek_bwd_nth(2), Some(&1));
+
+        // Full backward buffer.
+        it.nth(1);
+        assert_eq!(it.stream_pos(), 5);
+        assert_eq!(it.bwd_oldest_age(), Some(3));
+        let age = it.bwd_oldest_age().unwrap();
+        let oldest = it.stream_pos() - age;
+        assert_eq!(it.peek_bwd_nth(age - 1), Some(&a[oldest]));
+    }
+
+    #[test]
+    fn test_fwd_partition_point() {
+        let a = [1, 2, 3, 4, 10, 11, 12, 13];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 6>::new(a.iter().copied());
+
+        assert_eq!(it.fwd_partition_point(|&x| x < 10), 4);
+        assert_eq!(it.fwd_partition_point(|&x| x < 3), 2);
+        assert_eq!(it.fwd_partition_point(|_| false), 0);
+        assert_eq!(it.fwd_partition_point(|_| true), 6);
+        assert_eq!(it.stream_pos(), 0);
+
+        it.nth(4);
+        assert_eq!(it.fwd_partition_point(|&x| x < 13), 2);
+        assert_eq!(it.fwd_partition_point(|_| true), 3);
+    }
+
+    #[test]
+    fn test_set_record_sentinel() {
+        let mut it = Peekable::<Chars, 4, 2>::from_chars("ab\ncd\ne\nf");
+        it.set_record_sentinel('\n');
+
+        assert_eq!(it.next(), Some('a'));
+        assert_eq!(it.next(), Some('b'));
+        assert_eq!(it.peek_bwd_nth(1), Some(&'a'));
+        assert_eq!(it.next(), Some('\n'));
+        assert_eq!(it.peek_bwd(), None);
+        assert_eq!(it.stream_pos(), 3);
+
+        assert_eq!(it.next(), Some('c'));
+        assert_eq!(it.next(), Some('d'));
+        assert_eq!(it.peek_bwd_nth(0), Some(&'d'));
+        assert_eq!(it.peek_bwd_nth(1), Some(&'c'));
+        assert_eq!(it.peek_bwd_nth(2), None);
+
+        // Clearing is not reported as eviction and blocks rewinding.
+        assert_eq!(it.next_checked_bwd(), (Some('\n'), None));
+        assert!(!it.step_back());
+        assert_eq!(it.stream_pos(), 6);
+
+        it.clear_record_sentinel();
+        assert_eq!(it.next(), Some('e'));
+        assert_eq!(it.next(), Some('\n'));
+        assert_eq!(it.peek_bwd_nth(1), Some(&'e'));
+    }
+
+    #[test]
+    fn test_expect_slice() {
+        let a = [1, 2, 3, 4, 5];
+        let mut it = Peekable::<Copied<Iter<i32>>, 4, 1>::new(a.iter().copied());
+
+        // Full match.
+        assert_eq!(it.expect_slice(&[1, 2]), Ok(2));
+        assert_eq!(it.peek_bwd(), Some(&2));
+
+        // Early mismatch consumes the matched prefix only.
+        assert_eq!(it.expect_slice(&[3, 9, 5]), Err(1));
+        assert_eq!(it.stream_pos(), 3);
+        assert_eq!(it.peek_fwd(), Some(&4));
+        assert_eq!(it.expect_slice(&[9]), Err(0));
+        assert_eq!(it.stream_pos(), 3);
+
+        // Short stream.
+        assert_eq!(it.expect_slice(&[4, 5, 6]), Err(2));
+        assert_eq!(it.next(), None);
+        let empty: [i32; 0] = [];
+        assert_eq!(it.expect_slice(&empty), Ok(0));
+    }
+
+    #[test]
+    fn test_with_bwd_history() {
+        let a = [4, 5];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::with_bwd_history(&a, [&1, &2, &3]);
+
+        assert_eq!(it.peek_bwd(), Some(&&3));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&2));
+        assert_eq!(it.peek_bwd_nth(2), None);
+        assert_eq!(it.stream_pos(), 0);
+        it.debug_invariants();
+
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.peek_bwd_nth(1), Some(&&3));
+        assert_eq!(it.max_rewind(), 1);
+        assert_eq!(it.step_back_by(2), 1);
+        assert_eq!(it.peek_bwd(), Some(&&3));
+        assert_eq!(it.next(), Some(&4));
+
+        // A seeded element is never un-consumed, even after a pop_bwd.
+        let mut it = Peekable::<Iter<i32>, 2, 2>::with_bwd_history(&a, [&9]);
+        assert_eq!(it.next(), Some(&4));
+        assert_eq!(it.pop_bwd(), Some(&4));
+        assert_eq!(it.peek_bwd(), Some(&&9));
+        assert_eq!(it.max_rewind(), 0);
+        assert!(!it.step_back());
+        assert_eq!(it.next(), Some(&5));
+        it.debug_invariants();
+    }
+
+    #[test]
+    fn test_transaction_advanced() {
+        let a = [1, 2, 3, 4];
+        let mut it = Peekable::<Iter<i32>, 4, 4>::new(&a);
+        it.next();
+
+        let mut t = it.transaction();
+        assert_eq!(t.transaction_advanced(), 0);
+        assert_eq!(t.next(), Some(&2));
+        assert_eq!(t.transaction_advanced(), 1);
+        assert_eq!(t.peek_fwd_nth(1), Some(&&4));
+        assert_eq!(t.peek_bwd(), Some(&&2));
+        assert_eq!(t.transaction_advanced(), 1);
+        assert_eq!(t.next(), Some(&3));
+        assert_eq!(t.transaction_advanced(), 2);
+        assert_eq!(t.rollback(), Ok(()));
+
+        assert_eq!(it.stream_pos(), 1);
+        assert_eq!(it.transaction().transaction_advanced(), 0);
+        assert_eq!(it.next(), Some(&2));
+    }
+
+    #[test]
+    fn test_peek_fwd_scope() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Iter<i32>, 1, 2>::new(&a);
+
+        let doubled = it.peek_fwd_scope(|x| x.map(|&&x| x * 2));
+        assert_eq!(doubled, Some(2));
+        let is_two = it.peek_fwd_nth_scope(1, |x| x == Some(&&2));
+        assert!(is_two);
+        assert!(it.peek_fwd_nth_scope(2, |x| x.is_none()));
+
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.peek_fwd_scope(|x| x.copied()), Some(&2));
+    }
+
+    #[test]
+    fn test_skip_bom() {
+        let mut it = Peekable::<Chars, 1, 1>::from_chars("\u{FEFF}ab");
+        assert!(it.skip_bom());
+        assert_eq!(it.peek_bwd(), Some(&'\u{FEFF}'));
+        assert!(!it.skip_bom());
+        assert_eq!(it.next(), Some('a'));
+
+        let mut it = Peekable::<Chars, 1, 1>::from_chars("ab");
+        assert!(!it.skip_bom());
+        assert_eq!(it.stream_pos(), 0);
+        assert_eq!(it.next(), Some('a'));
+
+        let mut it = Peekable::<Chars, 1, 1>::from_chars("");
+        assert!(!it.skip_bom());
+    }
+
+    #[test]
+    fn test_position_fwd_within() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let mut it = Peekable::<Copied<Iter<i32>>, 2, 1>::new(a.iter().copied());
+
+        // Hit within the budget.
+        assert_eq!(it.position_fwd_within(3, |&x| x == 3), Ok(2));
+        assert_eq!(it.peek_bwd(), Some(&3));
+
+        // Budget exhausted.
+        assert_eq!(it.position_fwd_within(2, |&x| x == 6), Err(2));
+        assert_eq!(it.stream_pos(), 5);
+        assert_eq!(it.position_fwd_within(0, |_| true), Err(0));
+
+        // Stream exhausted.
+        assert_eq!(it.position_fwd_within(5, |&x| x == 9), Err(1));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_on_consume_exactly_once() {
+        let a = [1, 2, 3, 4];
+        let count = core::cell::Cell::new(0);
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a).on_consume(|_| {
+            count.set(count.get() + 1);
+        });
+
+        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+        assert_eq!(count.get(), 0);
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.peek_bwd(), Some(&&2));
+        assert_eq!(count.get(), 2);
+        assert_eq!(it.by_ref().count(), 2);
+        assert_eq!(count.get(), 4);
+        assert_eq!(count.get(), it.stream_pos());
+    }
+
+    #[test]
+    fn test_suggest_fwd_size() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 4>::new(a.iter().copied());
+
+        // Inside the window.
+        assert_eq!(it.suggest_fwd_size(|&x| x == 4), Some(3));
+        assert_eq!(it.suggest_fwd_size(|&x| x == 1), Some(0));
+        // Outside the window.
+        assert_eq!(it.suggest_fwd_size(|&x| x == 5), None);
+        assert_eq!(it.stream_pos(), 0);
+        assert_eq!(it.fwd_buffered(), 4);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_fwd_counts() {
+        let mut it = Peekable::<Chars, 1, 6>::from_chars("a1b11a2");
+
+        let counts = it.fwd_counts();
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&'a'], 2);
+        assert_eq!(counts[&'1'], 3);
+        assert_eq!(counts[&'b'], 1);
+        assert_eq!(counts.get(&'2'), None);
+        let digits: usize = counts
+            .iter()
+            .filter(|(c, _)| c.is_ascii_digit())
+            .map(|(_, n)| n)
+            .sum();
+        assert_eq!(digits, 3);
+        assert_eq!(it.stream_pos(), 0);
+    }
+
+    #[test]
+    fn test_range() {
+        let mut it = Peekable::<Range<i32>, 4, 4>::from_range(10..16);
+
+        assert_eq!(it.next(), Some(10));
+        assert_eq!(it.next(), Some(11));
+        assert_eq!(it.next(), Some(12));
+        assert!(it.step_back());
+        assert_eq!(it.next(), Some(12));
+        assert_eq!(it.peek_fwd_nth(2), Some(&15));
+
+        // Rewinding and re-consuming yields identical values.
+        let start = it.stream_pos();
+        let first: [Option<i32>; 3] = core::array::from_fn(|_| it.next());
+        assert_eq!(first, [Some(13), Some(14), Some(15)]);
+        assert!(it.rewind_to(start));
+        assert_eq!(it.stream_pos(), start);
+        let again: [Option<i32>; 3] = core::array::from_fn(|_| it.next());
+        assert_eq!(again, first);
+
+        // The fused range stays exhausted.
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next(), None);
+        assert!(it.rewind_to(4));
+        assert_eq!(it.next(), Some(14));
+        assert!(!it.rewind_to(6));
+        assert!(!it.rewind_to(0));
+        assert_eq!(it.stream_pos(), 5);
+        assert_eq!(it.remaining(), Some(1));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_split() {
+        let split = |a: &[i32]| {
+            Peekable::<Copied<Iter<i32>>, 2, 2>::new(a.iter().copied())
+                .split(|&x| x == 0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(split(&[1, 2, 0, 3]), vec![vec![1, 2], vec![3]]);
+        // Leading separator.
+        assert_eq!(split(&[0, 1]), vec![vec![], vec![1]]);
+        // Trailing separator.
+        assert_eq!(split(&[1, 0]), vec![vec![1], vec![]]);
+        // Consecutive separators.
+        assert_eq!(split(&[1, 0, 0, 2]), vec![vec![1], vec![], vec![2]]);
+        assert_eq!(split(&[0]), vec![vec![], vec![]]);
+        assert_eq!(split(&[]), vec![Vec::<i32>::new()]);
+    }
+
+    #[test]
+    fn test_assert_progress() {
+        let a = [1, 2, 3];
+        let mut it = Peekable::<Iter<i32>, 2, 2>::new(&a);
+
+        let prev = it.stream_pos();
+        assert_eq!(it.peek_fwd_nth(1), Some(&&2));
+        assert!(!it.assert_progress(prev));
+        assert_eq!(it.next(), Some(&1));
+        assert!(it.assert_progress(prev));
+
+        let prev = it.stream_pos();
+        assert!(it.step_back());
+        assert!(!it.assert_progress(prev));
+    }
+
+    #[test]
+    fn test_fwd_slices_mut() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let mut it = Peekable::<Copied<Iter<i32>>, 1, 4>::new(a.iter().copied());
+
+        // Make the ring buffer wrap around.
+        it.fill_fwd();
+        it.next();
+        it.next();
+        it.fill_fwd();
+        let (front, back) = it.fwd_slices_mut();
+        assert!(!front.is_empty());
+        assert!(!back.is_empty());
+        for x in front.iter_mut().chain(back.iter_mut()) {
+            *x *= 10;
+        }
+
+        assert_eq!(it.next(), Some(30));
+        assert_eq!(it.next(), Some(40));
+        assert_eq!(it.next(), Some(50));
+        assert_eq!(it.peek_bwd(), Some(&50));
+        assert_eq!(it.next(), Some(60));
+        assert_eq!(it.next(), None);
+    }
 }
 
 // vim: ts=4 sw=4 expandtab